@@ -0,0 +1,161 @@
+// The bit flipping attacks from challenges 16 and 26, without knowing the prefix.
+//
+// We find where our input ends up by asking for two encryptions that only differ in what we sent.
+// In CTR the ciphertexts differ exactly where our bytes are. In CBC they differ from the block
+// our first changed byte is in onwards, so change one byte at a time: the first byte that moves the
+// first differing block forward is the first byte of a block, which pins down where we start.
+//
+// Then send harmless filler of the right length and xor (filler xor what we want) into the
+// ciphertext. CTR flips the plaintext in place. CBC flips the next block's plaintext and scrambles
+// this one, so we send an extra block of filler first, to be the garbage.
+//
+// Both need the oracle to encrypt the same way every time (fixed IV or nonce), or there's no
+// difference to look at.
+
+use super::block_cipher::detect_block_size;
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Cbc,
+    Ctr,
+}
+
+const FILLER: u8 = b'A';
+const OTHER_FILLER: u8 = b'B';
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter().zip(b).position(|(x, y)| x != y)
+}
+
+/// where, in the ciphertext, the first byte of our input ends up
+pub fn locate_controlled_region<F: Fn(&[u8]) -> Result<Vec<u8>>>(
+    oracle: &F,
+    mode: Mode,
+) -> Result<usize> {
+    let changed_at = |i: usize, length: usize| -> Result<usize> {
+        let mut input = vec![FILLER; length];
+        let original = oracle(&input)?;
+        input[i] = OTHER_FILLER;
+        let changed = oracle(&input)?;
+        first_difference(&original, &changed)
+            .ok_or_else(|| anyhow!("changing our input didn't change the ciphertext"))
+    };
+    match mode {
+        Mode::Ctr => changed_at(0, 1),
+        Mode::Cbc => {
+            let (block_size, _) = detect_block_size(oracle, FILLER)?;
+            let first_block = changed_at(0, block_size + 1)? / block_size;
+            for i in 1..=block_size {
+                if changed_at(i, block_size + 1)? / block_size != first_block {
+                    // byte i starts the next block
+                    return Ok((first_block + 1) * block_size - i);
+                }
+            }
+            Err(anyhow!("couldn't find a block boundary in our input"))
+        }
+    }
+}
+
+/// a ciphertext that decrypts with `inject` somewhere in the middle, no matter what the oracle
+/// would have done to those bytes if we'd asked for them directly
+pub fn forge<F: Fn(&[u8]) -> Result<Vec<u8>>>(
+    oracle: &F,
+    mode: Mode,
+    inject: &[u8],
+) -> Result<Vec<u8>> {
+    let start = locate_controlled_region(oracle, mode)?;
+    let (input, target) = match mode {
+        Mode::Ctr => (vec![FILLER; inject.len()], start),
+        Mode::Cbc => {
+            let (block_size, _) = detect_block_size(oracle, FILLER)?;
+            if inject.len() > block_size {
+                // the block before each edited block is garbage, so one block is all we get
+                return Err(anyhow!(
+                    "can only inject up to {block_size} bytes in CBC, got {}",
+                    inject.len()
+                ));
+            }
+            // finish off the block we start in, then a block to scramble
+            let alignment = (block_size - start % block_size) % block_size;
+            let input = vec![FILLER; alignment + block_size + inject.len()];
+            (input, start + alignment)
+        }
+    };
+    let mut ciphertext = oracle(&input)?;
+    if target + inject.len() > ciphertext.len() {
+        return Err(anyhow!("the ciphertext is too short to hold our input"));
+    }
+    for (i, byte) in inject.iter().enumerate() {
+        ciphertext[target + i] ^= FILLER ^ byte;
+    }
+    Ok(ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::{cbc, ctr, Iv, Key};
+
+    const KEY: &[u8] = b"YELLOW SUBMARINE";
+    const IV: [u8; 16] = [3; 16];
+
+    fn quote(prefix: &[u8], input: &[u8]) -> Vec<u8> {
+        let mut plaintext = prefix.to_vec();
+        for &byte in input {
+            match byte {
+                b';' => plaintext.extend_from_slice(b"%3B"),
+                b'=' => plaintext.extend_from_slice(b"%3D"),
+                _ => plaintext.push(byte),
+            }
+        }
+        plaintext.extend_from_slice(b";comment2=%20like%20a%20pound%20of%20bacon");
+        plaintext
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn test_cbc() {
+        for prefix_length in [0, 5, 16, 31, 32] {
+            let prefix = vec![b'p'; prefix_length];
+            let oracle = |input: &[u8]| cbc::encrypt(&quote(&prefix, input), Key(KEY), Iv(&IV));
+            assert_eq!(
+                locate_controlled_region(&oracle, Mode::Cbc).unwrap(),
+                prefix_length
+            );
+            let forged = forge(&oracle, Mode::Cbc, b";admin=true;").unwrap();
+            let plaintext = cbc::decrypt(&forged, Key(KEY), Iv(&IV)).unwrap();
+            assert!(contains(&plaintext, b";admin=true;"));
+        }
+        // the IV on the front is just one more block
+        let oracle = |input: &[u8]| {
+            let mut output = IV.to_vec();
+            output.extend(cbc::encrypt(&quote(b"hi", input), Key(KEY), Iv(&IV))?);
+            Ok(output)
+        };
+        assert_eq!(locate_controlled_region(&oracle, Mode::Cbc).unwrap(), 18);
+        let oracle = |input: &[u8]| cbc::encrypt(&quote(b"", input), Key(KEY), Iv(&IV));
+        assert!(forge(&oracle, Mode::Cbc, &[b'x'; 17]).is_err());
+    }
+
+    #[test]
+    fn test_ctr() {
+        for prefix_length in [0, 5, 16, 31, 32] {
+            let prefix = vec![b'p'; prefix_length];
+            let oracle = |input: &[u8]| ctr::encrypt(&quote(&prefix, input), Key(KEY), 7);
+            assert_eq!(
+                locate_controlled_region(&oracle, Mode::Ctr).unwrap(),
+                prefix_length
+            );
+            let inject = b";admin=true;role=admin;and it can be longer than a block";
+            let forged = forge(&oracle, Mode::Ctr, inject).unwrap();
+            let plaintext = ctr::decrypt(&forged, Key(KEY), 7).unwrap();
+            assert!(contains(&plaintext, inject));
+        }
+    }
+}
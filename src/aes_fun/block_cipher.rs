@@ -0,0 +1,162 @@
+use super::Key;
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes192, Aes256};
+use anyhow::{anyhow, Result};
+
+// The modes (ecb, cbc, ctr, gcm) only ever need to encrypt or decrypt one block at a time, so
+// that's all a block cipher has to do for them. Anything implementing this works in every mode.
+
+pub trait BlockCipher {
+    fn block_size(&self) -> usize;
+    /// block.len() must be block_size()
+    fn encrypt_block(&self, block: &mut [u8]);
+    /// block.len() must be block_size()
+    fn decrypt_block(&self, block: &mut [u8]);
+}
+
+impl<C: BlockCipher + ?Sized> BlockCipher for &C {
+    fn block_size(&self) -> usize {
+        (**self).block_size()
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        (**self).encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        (**self).decrypt_block(block)
+    }
+}
+
+/// AES, with the key size picked by the length of the key
+pub enum Aes {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256),
+}
+
+impl Aes {
+    pub fn new(key: Key) -> Result<Aes> {
+        let key = key.0;
+        match key.len() {
+            16 => Ok(Aes::Aes128(Aes128::new(GenericArray::from_slice(key)))),
+            24 => Ok(Aes::Aes192(Aes192::new(GenericArray::from_slice(key)))),
+            32 => Ok(Aes::Aes256(Aes256::new(GenericArray::from_slice(key)))),
+            n => Err(anyhow!("AES keys are 16, 24 or 32 bytes, got {n}")),
+        }
+    }
+}
+
+impl BlockCipher for Aes {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            Aes::Aes128(cipher) => cipher.encrypt_block(block),
+            Aes::Aes192(cipher) => cipher.encrypt_block(block),
+            Aes::Aes256(cipher) => cipher.encrypt_block(block),
+        }
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            Aes::Aes128(cipher) => cipher.decrypt_block(block),
+            Aes::Aes192(cipher) => cipher.decrypt_block(block),
+            Aes::Aes256(cipher) => cipher.decrypt_block(block),
+        }
+    }
+}
+
+// nobody's using a block cipher with blocks bigger than this
+pub const MAX_BLOCK_SIZE: usize = 256;
+
+/// for anything that encrypts our input under a padded block mode: send more and more filler
+/// until the ciphertext grows, which it does by a block. returns (block size, how many bytes of
+/// filler it took)
+pub fn detect_block_size<F>(mut encrypt: F, filler: u8) -> Result<(usize, usize)>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>>,
+{
+    let initial_length = encrypt(&[])?.len();
+    for i in 1..=MAX_BLOCK_SIZE {
+        let length = encrypt(&vec![filler; i])?.len();
+        if length != initial_length {
+            if length < initial_length {
+                return Err(anyhow!(
+                    "the ciphertext got shorter when the input got longer"
+                ));
+            }
+            return Ok((length - initial_length, i));
+        }
+    }
+    Err(anyhow!(
+        "the ciphertext didn't grow after {MAX_BLOCK_SIZE} bytes of input"
+    ))
+}
+
+/// the test vectors from NIST SP 800-38A appendix F, shared between the modes' tests
+#[cfg(test)]
+pub(crate) mod sp800_38a {
+    pub const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172a\
+                                 ae2d8a571e03ac9c9eb76fac45af8e51\
+                                 30c81c46a35ce411e5fbc1191a0a52ef\
+                                 f69f2445df4f9b17ad2b417be66c3710";
+    pub const KEY_128: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    pub const KEY_192: &str = "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b";
+    pub const KEY_256: &str = "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4";
+    pub const IV: &str = "000102030405060708090a0b0c0d0e0f";
+    pub const COUNTER_BLOCK: &str = "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+
+    pub fn hex(s: &str) -> Vec<u8> {
+        crate::cryptopal_util::hex_to_bytes(s.to_string()).unwrap()
+    }
+}
+
+/// a terrible 8 byte block cipher, for tests that want a block size that isn't 16
+#[cfg(test)]
+pub(crate) struct ToyCipher(pub u8);
+
+#[cfg(test)]
+impl BlockCipher for ToyCipher {
+    fn block_size(&self) -> usize {
+        8
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        for byte in block.iter_mut() {
+            *byte = (*byte ^ self.0).rotate_left(3);
+        }
+        block.reverse();
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        block.reverse();
+        for byte in block.iter_mut() {
+            *byte = byte.rotate_right(3) ^ self.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aes_key_sizes() {
+        for size in [16, 24, 32] {
+            let cipher = Aes::new(Key(&vec![0; size])).unwrap();
+            let mut block = *b"YELLOW SUBMARINE";
+            cipher.encrypt_block(&mut block);
+            assert_ne!(&block, b"YELLOW SUBMARINE");
+            cipher.decrypt_block(&mut block);
+            assert_eq!(&block, b"YELLOW SUBMARINE");
+        }
+        for size in [0, 15, 17, 64] {
+            assert!(Aes::new(Key(&vec![0; size])).is_err());
+        }
+    }
+}
@@ -1,64 +1,129 @@
 use super::{Iv, Key};
+use crate::aes_fun::block_cipher::{Aes, BlockCipher};
 use crate::pkcs7::{pkcs7_pad, pkcs7_unpad};
-use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
-use aes::Aes128;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 pub fn encrypt(plaintext: &[u8], key: Key, iv: Iv) -> Result<Vec<u8>> {
-    let plaintext = pkcs7_pad(plaintext, 16);
-    let mut ciphertext = Vec::new();
-    let block_size = 16;
-    let key = GenericArray::from_slice(key.0);
-    let cipher = Aes128::new(key);
-    let mut block_spot = [0u8; 16];
+    encrypt_with(&Aes::new(key)?, plaintext, iv)
+}
+
+pub fn decrypt_no_unpad(ciphertext: &[u8], key: Key, iv: Iv) -> Result<Vec<u8>> {
+    decrypt_no_unpad_with(&Aes::new(key)?, ciphertext, iv)
+}
+
+pub fn decrypt(ciphertext: &[u8], key: Key, iv: Iv) -> Result<Vec<u8>> {
+    decrypt_with(&Aes::new(key)?, ciphertext, iv)
+}
+
+fn check_iv<C: BlockCipher>(cipher: &C, iv: &Iv) -> Result<()> {
+    if iv.0.len() != cipher.block_size() {
+        return Err(anyhow!(
+            "CBC IV must be {} bytes, got {}",
+            cipher.block_size(),
+            iv.0.len()
+        ));
+    }
+    Ok(())
+}
+
+pub fn encrypt_with<C: BlockCipher>(cipher: &C, plaintext: &[u8], iv: Iv) -> Result<Vec<u8>> {
+    check_iv(cipher, &iv)?;
+    let mut ciphertext = pkcs7_pad(plaintext, cipher.block_size());
     let mut previous_block = iv.0.to_vec();
-    for chunk in plaintext.chunks(block_size) {
-        block_spot.clone_from_slice(chunk);
-        block_spot
+    for block in ciphertext.chunks_mut(cipher.block_size()) {
+        block
             .iter_mut()
             .zip(previous_block.iter())
             .for_each(|(b1, b2)| {
                 *b1 ^= *b2;
             });
-        let block = GenericArray::from_mut_slice(&mut block_spot);
         cipher.encrypt_block(block);
-        previous_block = block.to_vec();
-        ciphertext.extend(block.as_slice());
+        previous_block.copy_from_slice(block);
     }
     Ok(ciphertext)
 }
 
-pub fn decrypt_no_unpad(ciphertext: &[u8], key: Key, iv: Iv) -> Vec<u8> {
-    let mut plaintext = Vec::new();
-    let block_size = 16;
-    assert_eq!(ciphertext.len() % block_size, 0);
-    assert_eq!(key.0.len(), block_size);
-    assert_eq!(iv.0.len(), block_size);
-    let key = GenericArray::from_slice(key.0);
-    let cipher = Aes128::new(key);
-    let mut block_spot = [0u8; 16];
-    let mut previous_block_spot = [0u8; 16];
-    for i in 0..ciphertext.len() / block_size {
-        if i == 0 {
-            previous_block_spot.clone_from_slice(iv.0);
+pub fn decrypt_no_unpad_with<C: BlockCipher>(
+    cipher: &C,
+    ciphertext: &[u8],
+    iv: Iv,
+) -> Result<Vec<u8>> {
+    check_iv(cipher, &iv)?;
+    let block_size = cipher.block_size();
+    if !ciphertext.len().is_multiple_of(block_size) {
+        return Err(anyhow!(
+            "CBC ciphertext must be a whole number of {block_size} byte blocks"
+        ));
+    }
+    let mut plaintext = ciphertext.to_vec();
+    for (i, block) in plaintext.chunks_mut(block_size).enumerate() {
+        let previous_block = if i == 0 {
+            iv.0
         } else {
-            previous_block_spot.clone_from_slice(&ciphertext[(i - 1) * block_size..i * block_size]);
-        }
-        block_spot.clone_from_slice(&ciphertext[i * block_size..(i + 1) * block_size]);
-        let block = GenericArray::from_mut_slice(&mut block_spot);
+            &ciphertext[(i - 1) * block_size..i * block_size]
+        };
         cipher.decrypt_block(block);
         block
             .iter_mut()
-            .zip(previous_block_spot.iter())
+            .zip(previous_block.iter())
             .for_each(|(b1, b2)| {
                 *b1 ^= *b2;
             });
-        plaintext.extend(block.as_slice());
     }
-    plaintext
+    Ok(plaintext)
 }
 
-pub fn decrypt(ciphertext: &[u8], key: Key, iv: Iv) -> Result<Vec<u8>> {
-    let plaintext = decrypt_no_unpad(ciphertext, key, iv);
+pub fn decrypt_with<C: BlockCipher>(cipher: &C, ciphertext: &[u8], iv: Iv) -> Result<Vec<u8>> {
+    let plaintext = decrypt_no_unpad_with(cipher, ciphertext, iv)?;
     pkcs7_unpad(plaintext.as_slice())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::block_cipher::sp800_38a::*;
+
+    #[test]
+    fn test_sp800_38a_vectors() {
+        let cases = [
+            (
+                KEY_128,
+                "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2\
+                 73bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7",
+            ),
+            (
+                KEY_192,
+                "4f021db243bc633d7178183a9fa071e8b4d9ada9ad7dedf4e5e738763f69145a\
+                 571b242012fb7ae07fa9baac3df102e008b0e27988598881d920a9e64f5615cd",
+            ),
+            (
+                KEY_256,
+                "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d\
+                 39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b",
+            ),
+        ];
+        for (key, ciphertext) in cases {
+            let key = hex(key);
+            let iv = hex(IV);
+            // the vectors are unpadded, so leave off our padding block
+            let my_ciphertext = encrypt(&hex(PLAINTEXT), Key(&key), Iv(&iv)).unwrap();
+            assert_eq!(my_ciphertext[..64], hex(ciphertext));
+            assert_eq!(
+                decrypt_no_unpad(&hex(ciphertext), Key(&key), Iv(&iv)).unwrap(),
+                hex(PLAINTEXT)
+            );
+            assert_eq!(
+                decrypt(&my_ciphertext, Key(&key), Iv(&iv)).unwrap(),
+                hex(PLAINTEXT)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_sizes() {
+        let key = b"YELLOW SUBMARINE";
+        assert!(encrypt(b"hello", Key(b"short key"), Iv(&[0; 16])).is_err());
+        assert!(encrypt(b"hello", Key(key), Iv(&[0; 8])).is_err());
+        assert!(decrypt(&[0; 17], Key(key), Iv(&[0; 16])).is_err());
+    }
+}
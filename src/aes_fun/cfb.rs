@@ -0,0 +1,181 @@
+use super::{Iv, Key};
+use crate::aes_fun::block_cipher::{Aes, BlockCipher};
+use anyhow::{anyhow, Result};
+
+// CFB: keep a shift register of the last block's worth of ciphertext (starting with the IV),
+// encrypt it, and xor the first s bytes of the result into the next s bytes of plaintext. Then
+// shift those s ciphertext bytes into the register and go again.
+//
+//  I_1 = IV, I_j = (I_(j-1) << s) | C_(j-1)
+//  C_j = P_j xor first s bytes of E(I_j)
+//
+// s is the segment size: CFB-8 takes one byte at a time (one block encryption per byte!), CFB-128
+// takes a whole block. Either way there's no padding, and decryption only ever needs E.
+//
+// Because the ciphertext feeds back, flipping a ciphertext bit flips the same plaintext bit and
+// then scrambles the next block's worth of plaintext, while it sits in the register.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentSize {
+    Cfb8,
+    Cfb128,
+}
+
+impl SegmentSize {
+    fn bytes(self) -> usize {
+        match self {
+            SegmentSize::Cfb8 => 1,
+            SegmentSize::Cfb128 => 16,
+        }
+    }
+}
+
+/// CFB as a stream: feed it data in pieces of any size
+pub struct CfbStream<C: BlockCipher> {
+    cipher: C,
+    decrypting: bool,
+    segment_size: usize,
+    register: Vec<u8>,
+    keystream_block: Vec<u8>,
+    // the ciphertext of the segment in progress, waiting to be shifted into the register
+    segment: Vec<u8>,
+}
+
+impl<C: BlockCipher> CfbStream<C> {
+    fn new(cipher: C, iv: Iv, segment_size: SegmentSize, decrypting: bool) -> Result<CfbStream<C>> {
+        if iv.0.len() != cipher.block_size() {
+            return Err(anyhow!(
+                "CFB IV must be {} bytes, got {}",
+                cipher.block_size(),
+                iv.0.len()
+            ));
+        }
+        if segment_size.bytes() > cipher.block_size() {
+            return Err(anyhow!("CFB segments can't be bigger than a block"));
+        }
+        let mut keystream_block = iv.0.to_vec();
+        cipher.encrypt_block(&mut keystream_block);
+        Ok(CfbStream {
+            cipher,
+            decrypting,
+            segment_size: segment_size.bytes(),
+            register: iv.0.to_vec(),
+            keystream_block,
+            segment: vec![],
+        })
+    }
+
+    pub fn encryptor(cipher: C, iv: Iv, segment_size: SegmentSize) -> Result<CfbStream<C>> {
+        CfbStream::new(cipher, iv, segment_size, false)
+    }
+
+    pub fn decryptor(cipher: C, iv: Iv, segment_size: SegmentSize) -> Result<CfbStream<C>> {
+        CfbStream::new(cipher, iv, segment_size, true)
+    }
+
+    pub fn process(&mut self, input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(input.len());
+        for &byte in input.iter() {
+            let out = byte ^ self.keystream_block[self.segment.len()];
+            output.push(out);
+            // the ciphertext byte is the one that feeds back
+            self.segment.push(if self.decrypting { byte } else { out });
+            if self.segment.len() == self.segment_size {
+                self.register.drain(..self.segment_size);
+                self.register.append(&mut self.segment);
+                self.keystream_block.copy_from_slice(&self.register);
+                self.cipher.encrypt_block(&mut self.keystream_block);
+            }
+        }
+        output
+    }
+}
+
+pub fn encrypt(plaintext: &[u8], key: Key, iv: Iv, segment_size: SegmentSize) -> Result<Vec<u8>> {
+    Ok(CfbStream::encryptor(Aes::new(key)?, iv, segment_size)?.process(plaintext))
+}
+
+pub fn decrypt(ciphertext: &[u8], key: Key, iv: Iv, segment_size: SegmentSize) -> Result<Vec<u8>> {
+    Ok(CfbStream::decryptor(Aes::new(key)?, iv, segment_size)?.process(ciphertext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::block_cipher::sp800_38a::*;
+
+    fn check(key: &str, segment_size: SegmentSize, plaintext: &[u8], ciphertext: &[u8]) {
+        let key = hex(key);
+        let iv = hex(IV);
+        assert_eq!(
+            encrypt(plaintext, Key(&key), Iv(&iv), segment_size).unwrap(),
+            ciphertext
+        );
+        assert_eq!(
+            decrypt(ciphertext, Key(&key), Iv(&iv), segment_size).unwrap(),
+            plaintext
+        );
+        // any way of chopping up the input gives the same stream
+        let cipher = Aes::new(Key(&key)).unwrap();
+        let mut stream = CfbStream::decryptor(cipher, Iv(&iv), segment_size).unwrap();
+        let mut pieces = stream.process(&ciphertext[..3]);
+        pieces.extend(stream.process(&ciphertext[3..17]));
+        pieces.extend(stream.process(&ciphertext[17..]));
+        assert_eq!(pieces, plaintext);
+    }
+
+    #[test]
+    fn test_sp800_38a_cfb128_vectors() {
+        let cases = [
+            (
+                KEY_128,
+                "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+                 26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6",
+            ),
+            (
+                KEY_192,
+                "cdc80d6fddf18cab34c25909c99a417467ce7f7f81173621961a2b70171d3d7a\
+                 2e1e8a1dd59b88b1c8e60fed1efac4c9c05f9f9ca9834fa042ae8fba584b09ff",
+            ),
+            (
+                KEY_256,
+                "dc7e84bfda79164b7ecd8486985d386039ffed143b28b1c832113c6331e5407b\
+                 df10132415e54b92a13ed0a8267ae2f975a385741ab9cef82031623d55b1e471",
+            ),
+        ];
+        for (key, ciphertext) in cases {
+            check(key, SegmentSize::Cfb128, &hex(PLAINTEXT), &hex(ciphertext));
+        }
+    }
+
+    #[test]
+    fn test_sp800_38a_cfb8_vectors() {
+        // the CFB-8 vectors only cover the first 18 bytes
+        let cases = [
+            (KEY_128, "3b79424c9c0dd436bace9e0ed4586a4f32b9"),
+            (KEY_192, "cda2521ef0a905ca44cd057cbf0d47a0678a"),
+            (KEY_256, "dc1f1a8520a64db55fcc8ac554844e889700"),
+        ];
+        for (key, ciphertext) in cases {
+            check(
+                key,
+                SegmentSize::Cfb8,
+                &hex(PLAINTEXT)[..18],
+                &hex(ciphertext),
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_sizes() {
+        let iv = [0; 16];
+        assert!(encrypt(b"hello", Key(b"short key"), Iv(&iv), SegmentSize::Cfb8).is_err());
+        assert!(encrypt(
+            b"hello",
+            Key(b"YELLOW SUBMARINE"),
+            Iv(&iv[..8]),
+            SegmentSize::Cfb8
+        )
+        .is_err());
+    }
+}
@@ -28,7 +28,7 @@ pub fn oracle(my_input: &[u8]) -> (bool, Vec<u8>) {
 
     let coin_flip = rand::thread_rng().gen_range(0..2);
     if coin_flip == 1 {
-        let my_ciphertext = ecb::encrypt(&my_padded_input, Key(&random_key));
+        let my_ciphertext = ecb::encrypt(&my_padded_input, Key(&random_key)).unwrap();
         (true, my_ciphertext)
     } else {
         let random_iv: Vec<u8> = (&mut rng).sample_iter(Standard).take(16).collect();
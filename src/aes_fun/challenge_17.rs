@@ -7,7 +7,7 @@
 //
 // The second function should consume the ciphertext produced by the first function, decrypt it, check its padding, and return true or false depending on whether the padding is valid.
 
-use crate::pkcs7::pkcs7_unpad;
+use crate::aes_fun::padding_oracle::{PaddingOracle, PaddingOracleAttacker};
 use crate::random_things::{MY_RANDOM_IV, MY_RANDOM_KEY};
 use crate::{aes_fun, cryptopal_util};
 use aes_fun::{Iv, Key};
@@ -29,67 +29,29 @@ lazy_static! {
     ];
 }
 
-// pick a string at random
-fn encryption_oracle() -> Result<Vec<u8>> {
+// pick a string at random, and hand back the iv and ciphertext
+fn encryption_oracle() -> Result<(Vec<u8>, Vec<u8>)> {
     // pick a random string from STRINGS
     let random_string = &STRINGS[rand::random::<usize>() % STRINGS.len()];
     // decode the string
     let decoded_string = cryptopal_util::b64_to_bytes(random_string.to_string())?;
     // encrypt the string
-    aes_fun::cbc::encrypt(&decoded_string, Key(&MY_RANDOM_KEY), Iv(&MY_RANDOM_IV))
+    let ciphertext =
+        aes_fun::cbc::encrypt(&decoded_string, Key(&MY_RANDOM_KEY), Iv(&MY_RANDOM_IV))?;
+    Ok((MY_RANDOM_IV.to_vec(), ciphertext))
 }
 
-fn decryption_oracle(ciphertext: &[u8]) -> Result<()> {
-    let _ = aes_fun::cbc::decrypt(ciphertext, Key(&MY_RANDOM_KEY), Iv(&MY_RANDOM_IV))?;
-    Ok(())
+struct DecryptionOracle;
+
+impl PaddingOracle for DecryptionOracle {
+    fn is_valid(&self, iv: &[u8], ct: &[u8]) -> bool {
+        aes_fun::cbc::decrypt(ct, Key(&MY_RANDOM_KEY), Iv(iv)).is_ok()
+    }
 }
 
 pub fn challenge_17_attack() -> Result<Vec<u8>> {
     // get our encrypted string
-    let ciphertext = encryption_oracle()?;
-    let n_blocks = ciphertext.len() / 16;
-    assert!(n_blocks > 1);
-    let mut decryption_vec = vec![];
-    for i in 0..n_blocks {
-        let attack_block = if i > 0 {
-            ciphertext[(i - 1) * 16..(i) * 16].to_vec()
-        } else {
-            MY_RANDOM_IV.to_vec()
-        };
-        let target_block = ciphertext[(i) * 16..(i + 1) * 16].to_vec();
-        let mut decrypted = [0u8; 16];
-        let mut blank_it_out = [0u8; 16];
-        for byte in (0..16).rev() {
-            for change in 0..=255 {
-                let mut new_ciphertext = attack_block.clone();
-                new_ciphertext[byte] = change;
-                // set the rest of the block after byte to decrypted ^ target_length
-                for byte_to_set in (byte + 1)..=15 {
-                    new_ciphertext[byte_to_set] = blank_it_out[byte_to_set] ^ (16 - byte) as u8;
-                }
-                new_ciphertext.extend_from_slice(&target_block);
-                if decryption_oracle(&new_ciphertext).is_ok() {
-                    // If the padding is correct, the attacker now knows that the last byte of
-                    // D_{K}(C_{2}) ^  C_{1}' is 0x01, the last two bytes are 0x02, the last three bytes are 0x03, …,
-                    // or the last eight bytes are 0x08. The attacker can modify the second-last byte (flip any bit) to ensure that the last byte is 0x01.
-                    if byte != 0 {
-                        // if we're working on byte = 15, we get 1 and 2, if we're working on byte = 14, we get 2 and 3, etc.
-                        let padding_val: u8 = (16 - byte) as u8;
-                        new_ciphertext[byte - 1] ^= 1;
-                        if decryption_oracle(&new_ciphertext).is_ok() {
-                            blank_it_out[byte] = padding_val ^ change;
-                            decrypted[byte] = blank_it_out[byte] ^ attack_block[byte];
-                            break;
-                        }
-                    } else {
-                        blank_it_out[byte] = 16 ^ change;
-                        decrypted[byte] = blank_it_out[byte] ^ attack_block[byte];
-                        break;
-                    }
-                }
-            }
-        }
-        decryption_vec.extend_from_slice(&decrypted);
-    }
-    pkcs7_unpad(&decryption_vec)
+    let (iv, ciphertext) = encryption_oracle()?;
+    let mut attacker = PaddingOracleAttacker::new(&DecryptionOracle, 16);
+    attacker.decrypt(&iv, &ciphertext)
 }
@@ -1,66 +1,46 @@
+use crate::aes_fun::fixed_nonce_ctr::{break_fixed_nonce, decrypt_all, KeystreamByte};
+use crate::aes_fun::{ctr, Key};
 use crate::cryptopal_util;
-use crate::englishness::break_repeating_key_xor_with_keysize;
+use crate::englishness::crib_drag::CribDragger;
+use crate::random_things::MY_RANDOM_KEY;
 use anyhow::Result;
 
-pub fn attack() -> Result<Vec<u8>> {
-    let lines = cryptopal_util::read_lines_from_file("./data/20.txt".to_string())?;
-    let bytes = lines
+/// base64 decode each line of the file
+pub fn read_plaintexts(filename: &str) -> Result<Vec<Vec<u8>>> {
+    cryptopal_util::read_lines_from_file(filename.to_string())?
+        .into_iter()
+        .map(cryptopal_util::b64_to_bytes)
+        .collect()
+}
+
+/// CTR encrypt each plaintext, all under the same key and nonce
+pub fn encrypt_all(plaintexts: &[Vec<u8>]) -> Result<Vec<Vec<u8>>> {
+    plaintexts
         .iter()
-        .map(|line| cryptopal_util::b64_to_bytes(line.to_string()))
-        .collect::<Result<Vec<Vec<u8>>>>()?;
-    // take minimum of all the bytes' lengths
-    let min_len = bytes.iter().map(|b| b.len()).min().unwrap();
-    // truncate all bytes to min_len and append them together
-    let mut bytes_concat = Vec::new();
-    for b in bytes {
-        bytes_concat.extend_from_slice(&b[..min_len]);
-    }
-    let (_score, mut key, _decrypted) =
-        break_repeating_key_xor_with_keysize(bytes_concat.clone(), min_len as u32);
+        .map(|plaintext| ctr::encrypt(plaintext, Key(&MY_RANDOM_KEY), 0))
+        .collect()
+}
 
-    // ok now we got most of the thing decrypted. time to fix the random other bytes that are messed up...
-    //"warnung,"
-    key[0] ^= b'n' ^ b'I';
-    key[5] ^= b'R' ^ b's';
-    key[7] ^= b's' ^ b'u';
-    key[8] ^= b'_' ^ b's';
-    key[16] ^= b'l' ^ b'p';
-    key[17] ^= b'e' ^ b' ';
-    key[30] ^= b'u' ^ b'i';
-    key[47] ^= b'`' ^ b'a';
+/// returns (what we think the plaintexts are, the keystream we found)
+pub fn attack_file(filename: &str) -> Result<(Vec<Vec<u8>>, Vec<KeystreamByte>)> {
+    let ciphertexts = encrypt_all(&read_plaintexts(filename)?)?;
+    let keystream = break_fixed_nonce(&ciphertexts);
+    Ok((decrypt_all(&ciphertexts, &keystream), keystream))
+}
 
-    let dist_index = 0;
-    //key[dist_index] = trial;
-    let patched_decrypted = cryptopal_util::repeating_key_xor(&bytes_concat, &key);
-    let patched_decrypted_chunks = patched_decrypted.chunks(53);
-    for i in 25..53 {
-        if i % 10 == 0 {
-            print!("    {}", i / 10);
-        } else {
-            print!("     ");
-        }
-    }
-    println!();
-    for i in 25..53 {
-        print!("    {}", i % 10);
-    }
-    println!();
-    let mut dist = vec![];
-    for chunk in patched_decrypted_chunks.take(30) {
-        for (i, letter) in chunk[25..53].iter().enumerate() {
-            if i == dist_index {
-                dist.push(*letter)
-            }
-            print!("    {}", *letter as char);
-        }
-        println!();
-    }
-    let parse_dist = cryptopal_util::bytes_to_ascii(&dist)?;
-    println!("DIST: {parse_dist}");
-    println!(
-        "{}",
-        cryptopal_util::bytes_to_ascii(&patched_decrypted[..24 * 30])?
-    );
+/// the recovered lines of data/20.txt, one per line
+pub fn attack() -> Result<Vec<u8>> {
+    let (recovered, _keystream) = attack_file("./data/20.txt")?;
+    Ok(recovered.join(&b'\n'))
+}
 
-    Ok(patched_decrypted)
+/// a crib dragging session over the lines of the file, starting from the keystream bytes the
+/// automatic solver is sure of
+pub fn crib_drag_session(filename: &str) -> Result<CribDragger> {
+    let ciphertexts = encrypt_all(&read_plaintexts(filename)?)?;
+    let keystream = break_fixed_nonce(&ciphertexts)
+        .iter()
+        .map(|k| (k.confidence > 0.9).then_some(k.value))
+        .collect::<Vec<_>>();
+    Ok(CribDragger::with_keystream(ciphertexts, &keystream))
 }
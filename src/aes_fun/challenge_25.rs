@@ -9,22 +9,28 @@
 //
 // Recover the original plaintext.
 
-use crate::aes_fun::{ctr, Key};
+use crate::aes_fun::block_cipher::Aes;
+use crate::aes_fun::ctr::{self, CtrParams, CtrStream};
+use crate::aes_fun::Key;
 use crate::cryptopal_util;
 use crate::random_things::MY_RANDOM_KEY;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::iter;
 
 pub fn encrypt(plaintext: &[u8]) -> Vec<u8> {
-    ctr::encrypt(plaintext, Key(&MY_RANDOM_KEY), 0)
+    ctr::encrypt(plaintext, Key(&MY_RANDOM_KEY), 0).unwrap()
 }
 
 pub fn edit(ciphertext: &[u8], key: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
-    let mut plaintext = ctr::decrypt(ciphertext, Key(key), 0)?;
-    for (i, newbyte) in newtext.iter().enumerate() {
-        plaintext[offset + i] = *newbyte;
+    if offset + newtext.len() > ciphertext.len() {
+        return Err(anyhow!("edit runs past the end of the ciphertext"));
     }
-    Ok(ctr::encrypt(&plaintext, Key(key), 0))
+    // only the edited bytes need any keystream
+    let mut stream = CtrStream::new(Aes::new(Key(key))?, CtrParams::cryptopals(0))?;
+    stream.seek(offset as u64);
+    let mut edited = ciphertext.to_vec();
+    edited[offset..offset + newtext.len()].copy_from_slice(&stream.process(newtext));
+    Ok(edited)
 }
 
 pub fn edit_api(ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
@@ -1,3 +1,4 @@
+use crate::aes_fun::bitflipping::{self, Mode};
 use crate::random_things::MY_RANDOM_KEY;
 use crate::{aes_fun, cryptopal_util};
 use aes_fun::Key;
@@ -12,17 +13,14 @@ use anyhow::Result;
 // The function should quote out the ";" and "=" characters.
 //
 // The function should then pad out the input to the 16-byte AES block length and encrypt it under the random AES key.
-fn oracle(input_string: String) -> Result<Vec<u8>> {
+fn oracle(input: &[u8]) -> Result<Vec<u8>> {
+    let input_string = cryptopal_util::bytes_to_ascii(input)?;
     let quoted_out = input_string.replace(';', "%3B").replace('=', "%3D");
     let mut output = "comment1=cooking%20MCs;userdata=".to_string();
     output.push_str(&quoted_out);
     output.push_str(";comment2=%20like%20a%20pound%20of%20bacon");
     let output_bytes = cryptopal_util::ascii_to_bytes(&output)?;
-    Ok(aes_fun::ctr::encrypt(
-        &output_bytes,
-        aes_fun::Key(&MY_RANDOM_KEY),
-        0,
-    ))
+    aes_fun::ctr::encrypt(&output_bytes, aes_fun::Key(&MY_RANDOM_KEY), 0)
 }
 
 // The second function should decrypt the string and look for the characters ";admin=true;" (or, equivalently, decrypt, split the string on ";", convert each resulting string into 2-tuples, and look for the "admin" tuple).
@@ -47,19 +45,7 @@ fn target(input_bytes: &[u8]) -> Result<bool> {
 // Produces the identical 1-bit error(/edit) in the next ciphertext block.
 
 pub fn attack() -> Result<bool> {
-    let length_1 = "comment1=cooking%20MCs;userdata=".len();
-    let output = [b'A'; 16 * 8];
-    let mut encrypted = oracle(cryptopal_util::bytes_to_ascii(&output)?)?;
-    let jokes_and_tricks = cryptopal_util::fixed_xor(
-        &cryptopal_util::ascii_to_bytes(";admin=true;")?,
-        &cryptopal_util::ascii_to_bytes("AAAAAAAAAAAA")?,
-    );
-    // round length_1 up to a multiple of 16
-    let length_1_rounded = (length_1 + 15) / 16 * 16 + 16;
-    // xor the block starting at length_1_rounded with jokes_and_tricks
-    for i in 0..jokes_and_tricks.len() {
-        encrypted[length_1_rounded + i] ^= jokes_and_tricks[i];
-    }
-
+    // find where our input lands and flip it from the ciphertext, without knowing the prefix
+    let encrypted = bitflipping::forge(&oracle, Mode::Ctr, b";admin=true;")?;
     target(&encrypted)
 }
@@ -12,7 +12,7 @@ fn oracle(input_bytes: &[u8]) -> Result<Vec<u8>> {
 fn target(input_bytes: &[u8]) -> Result<bool> {
     // decrypt the string
     let decrypted_bytes =
-        aes_fun::cbc::decrypt_no_unpad(input_bytes, Key(&MY_RANDOM_KEY), Iv(&MY_RANDOM_KEY));
+        aes_fun::cbc::decrypt_no_unpad(input_bytes, Key(&MY_RANDOM_KEY), Iv(&MY_RANDOM_KEY))?;
     let decrypted_string = unsafe { String::from_utf8_unchecked(decrypted_bytes) };
     if decrypted_string.is_ascii() {
         // return whether it contains the characters ";admin=true;"
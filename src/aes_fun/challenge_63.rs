@@ -0,0 +1,109 @@
+// Key-Recovery Attacks on GCM with Repeated Nonces
+// GCM is the most widely used block cipher mode. It's an authenticated encryption mode: CTR for
+// confidentiality, plus a polynomial MAC, GMAC, for integrity.
+//
+// GMAC turns the AAD and ciphertext blocks into the coefficients of a polynomial and evaluates it
+// at the authentication key H, then masks the result with one block of keystream:
+//
+//  t = c0*h^n + c1*h^(n-1) + ... + cn*h + s
+//
+// If a nonce is ever reused, the mask s is reused too. Take two messages under the same nonce and
+// add their tag equations together: s cancels out, and what's left is a polynomial with known
+// coefficients that has H as a root.
+//
+//  f(y) = (c0*y^n + ... + t) + (d0*y^m + ... + t')
+//
+// Factor it over GF(2^128) and the linear factors give you a short list of candidates for H. A
+// third message under the same nonce narrows the list, and once you have H (and then s) you can
+// forge a valid tag for any ciphertext you like.
+
+use crate::aes_fun::gcm;
+use crate::aes_fun::gf128::Gf128;
+use crate::aes_fun::gf128_polynomial::{roots, Polynomial};
+use crate::aes_fun::Key;
+use crate::random_things::MY_RANDOM_KEY;
+use anyhow::{anyhow, Result};
+
+// the careless server always encrypts under the same nonce
+const REUSED_NONCE: [u8; 12] = *b"oops nonce!!";
+const AAD: &[u8] = b"user=dog;v=1";
+
+struct Message {
+    aad: Vec<u8>,
+    ciphertext: Vec<u8>,
+    tag: [u8; 16],
+}
+
+fn encryption_oracle(plaintext: &[u8]) -> Result<Message> {
+    let (ciphertext, tag) = gcm::encrypt(plaintext, AAD, Key(&MY_RANDOM_KEY), &REUSED_NONCE)?;
+    Ok(Message {
+        aad: AAD.to_vec(),
+        ciphertext,
+        tag,
+    })
+}
+
+fn decryption_oracle(message: &Message) -> Result<Vec<u8>> {
+    gcm::decrypt(
+        &message.ciphertext,
+        &message.aad,
+        &message.tag,
+        Key(&MY_RANDOM_KEY),
+        &REUSED_NONCE,
+    )
+}
+
+/// the tag equation for one message, as a polynomial in y whose value at H is the mask s
+fn tag_polynomial(message: &Message) -> Polynomial {
+    let blocks = gcm::ghash_blocks(&message.aad, &message.ciphertext);
+    // the first block gets the highest power of H, the last block gets H^1
+    let mut coefficients = vec![Gf128::from_block(&message.tag)];
+    coefficients.extend(blocks.into_iter().rev());
+    Polynomial::new(coefficients)
+}
+
+fn candidate_keys(first: &Message, second: &Message) -> Vec<Gf128> {
+    roots(&tag_polynomial(first).add(&tag_polynomial(second)))
+}
+
+pub fn attack() -> Result<()> {
+    // three messages under the same nonce
+    let m1 = encryption_oracle(b"send the dog a treat")?;
+    let m2 = encryption_oracle(b"the dog has been good")?;
+    let m3 = encryption_oracle(b"a very good dog, yes")?;
+
+    // every candidate from the first pair that also fits the second pair
+    let also_fits = candidate_keys(&m1, &m3);
+    let candidates: Vec<Gf128> = candidate_keys(&m1, &m2)
+        .into_iter()
+        .filter(|h| also_fits.contains(h))
+        .collect();
+    if candidates.len() != 1 {
+        return Err(anyhow!(
+            "expected exactly one candidate, got {}",
+            candidates.len()
+        ));
+    }
+    let h = candidates[0];
+    assert_eq!(h, gcm::authentication_key(Key(&MY_RANDOM_KEY))?);
+
+    // with H in hand, s falls right out of any of the tag equations
+    let s = tag_polynomial(&m1).evaluate(h);
+
+    // flip the plaintext under m1 (it's just CTR) and sign the result ourselves
+    let mut forged_ciphertext = m1.ciphertext.clone();
+    let original = b"send the dog a treat";
+    let wanted = b"send the cat a treat";
+    for i in 0..original.len() {
+        forged_ciphertext[i] ^= original[i] ^ wanted[i];
+    }
+    let forged_tag = gcm::ghash(h, &m1.aad, &forged_ciphertext) + s;
+    let forgery = Message {
+        aad: m1.aad.clone(),
+        ciphertext: forged_ciphertext,
+        tag: forged_tag.to_block(),
+    };
+    let plaintext = decryption_oracle(&forgery)?;
+    assert_eq!(plaintext, wanted);
+    Ok(())
+}
@@ -0,0 +1,305 @@
+// Key-Recovery Attacks on GCM with a Truncated MAC
+// This one is a little trickier, but it's Ferguson's classic "Authentication weaknesses in GCM".
+//
+// GCM lets you truncate the tag. Say the receiver only checks 32 bits of it. Then a random forgery
+// succeeds with probability 2^-32, which sounds fine... until you notice that we get to pick the
+// error polynomial, and some error polynomials are much luckier than others.
+//
+// Flip some ciphertext blocks. The change in the GHASH output is
+//
+//  e = sum of d_i * h^i
+//
+// where d_i is the difference in the block that gets multiplied by h^i. Only touch the blocks at
+// powers of two, and since squaring is linear over GF(2), e becomes a *linear* function of h:
+//
+//  e = sum of d_i * h^(2^i)
+//  Ad = sum of M_(d_i) * Ms^i
+//  e = Ad * h
+//
+// where M_c is the 128x128 "multiply by c" matrix and Ms is the squaring matrix. Every bit of Ad is
+// linear in the bits of the d_i, so we can ask linear algebra for the d_i that zero out the first
+// few rows of Ad. Zero k rows and the first k bits of the tag can't change, whatever h is; the
+// forgery now succeeds with probability 2^-(32 - k) rather than 2^-32.
+//
+// When a forgery does get through, the remaining rows of Ad (up to the tag length) must have
+// dotted to zero with h: free linear equations in the bits of h. Collect them in a matrix K, and
+// from then on work inside the kernel of K (h = X * h' for a basis X of it). Fewer unknowns means
+// more rows can be zeroed, which means cheaper forgeries, which means more equations, and so on
+// until the kernel is a single vector: h.
+//
+// The challenge uses a 32 bit tag and a 2^17 block message. That takes a while with our bit-by-bit
+// GF(2^128) multiply, so attack() uses a 16 bit tag and a 2^9 block message -- the attack is
+// exactly the same, the numbers are just smaller. The real numbers are an ignored test.
+
+use crate::aes_fun::gcm;
+use crate::aes_fun::gf128::Gf128;
+use crate::aes_fun::gf2_matrix::Gf2Matrix;
+use crate::aes_fun::Key;
+use crate::random_things::sixteen_random_bytes;
+use anyhow::{anyhow, Result};
+
+pub const TAG_SIZE: usize = 2;
+pub const N: usize = 9;
+// tag bits left free for the forgeries to learn from
+const LEARN_BITS: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub tag: Vec<u8>,
+}
+
+/// the other end of the wire: it sends GCM messages with truncated tags, and tells you whether it
+/// accepted whatever you send back
+pub struct Receiver {
+    key: Vec<u8>,
+    tag_size: usize,
+    pub queries: usize,
+}
+
+impl Receiver {
+    pub fn new(tag_size: usize) -> Receiver {
+        Receiver {
+            key: sixteen_random_bytes(),
+            tag_size,
+            queries: 0,
+        }
+    }
+
+    pub fn tag_size(&self) -> usize {
+        self.tag_size
+    }
+
+    pub fn send(&self, plaintext: &[u8]) -> Result<Message> {
+        let nonce = sixteen_random_bytes()[..12].to_vec();
+        let (ciphertext, tag) =
+            gcm::encrypt_truncated(plaintext, &[], Key(&self.key), &nonce, self.tag_size)?;
+        Ok(Message {
+            nonce,
+            ciphertext,
+            tag,
+        })
+    }
+
+    pub fn accepts(&mut self, message: &Message) -> bool {
+        self.queries += 1;
+        gcm::decrypt_truncated(
+            &message.ciphertext,
+            &[],
+            &message.tag,
+            Key(&self.key),
+            &message.nonce,
+            self.tag_size,
+        )
+        .is_ok()
+    }
+
+    /// for checking our work, not for the attacker
+    pub fn authentication_key(&self) -> Gf128 {
+        gcm::authentication_key(Key(&self.key)).unwrap()
+    }
+}
+
+fn length_block(ciphertext_len: usize) -> Gf128 {
+    *gcm::ghash_blocks(&[], &vec![0; ciphertext_len])
+        .last()
+        .unwrap()
+}
+
+/// the ciphertext blocks of `message` at the end of a `forged_blocks` long ciphertext (so they keep
+/// their powers of h), with d_i xored into the block that gets multiplied by h^(2^i)
+fn forge(message: &Message, d: &[Gf128], forged_blocks: usize) -> Message {
+    let mut ciphertext = vec![0u8; forged_blocks * 16 - message.ciphertext.len()];
+    ciphertext.extend_from_slice(&message.ciphertext);
+    for (i, d_i) in d.iter().enumerate() {
+        // block b (from 0) of an m block ciphertext gets multiplied by h^(m + 1 - b)
+        let b = forged_blocks + 1 - (1 << (i + 1));
+        for (byte, d_byte) in ciphertext[b * 16..(b + 1) * 16]
+            .iter_mut()
+            .zip(d_i.to_block())
+        {
+            *byte ^= d_byte;
+        }
+    }
+    Message {
+        nonce: message.nonce.clone(),
+        ciphertext,
+        tag: message.tag.clone(),
+    }
+}
+
+/// Ad = M_(length difference) + sum of M_(d_i) * Ms^i
+fn ad_matrix(d: &[Gf128], length_difference: Gf128, squarings: &[Gf2Matrix]) -> Gf2Matrix {
+    d.iter().zip(squarings.iter()).fold(
+        length_difference.multiplication_matrix(),
+        |acc, (d_i, ms_i)| acc.add(&d_i.multiplication_matrix().mul(ms_i)),
+    )
+}
+
+/// Ferguson's attack. The forgeries touch the coefficients of h^2, h^4, ..., h^(2^n).
+///
+/// with extend_ciphertext, the forgeries are allowed to be longer than the captured message
+/// (challenge 65). otherwise the captured message needs at least 2^n - 1 blocks of its own.
+pub fn recover_authentication_key(
+    receiver: &mut Receiver,
+    message: &Message,
+    n: usize,
+    extend_ciphertext: bool,
+) -> Result<Gf128> {
+    if !message.ciphertext.len().is_multiple_of(16) {
+        return Err(anyhow!(
+            "the captured ciphertext must be a whole number of blocks"
+        ));
+    }
+    let tag_bits = receiver.tag_size() * 8;
+    let captured_blocks = message.ciphertext.len() / 16;
+    let forged_blocks = if extend_ciphertext {
+        captured_blocks.max(1 << n)
+    } else {
+        captured_blocks
+    };
+    if forged_blocks + 1 < 1 << n {
+        return Err(anyhow!(
+            "need at least {} blocks to reach h^(2^{n}), have {forged_blocks}",
+            (1 << n) - 1
+        ));
+    }
+    // changing the length changes the length block, which adds a (known) constant term to e
+    let length_difference =
+        length_block(forged_blocks * 16) + length_block(message.ciphertext.len());
+
+    // Ms^1 .. Ms^n
+    let ms = Gf128::squaring_matrix();
+    let mut squarings = vec![ms.clone()];
+    for _ in 1..n {
+        squarings.push(squarings.last().unwrap().mul(&ms));
+    }
+
+    // K: everything we've learned about h, as equations K * h = 0
+    let mut k_matrix = Gf2Matrix::zero(0, 128);
+    // X: basis of the space h is still hiding in, one basis vector per column
+    let mut x_basis = Gf2Matrix::identity(128);
+    while x_basis.cols() > 1 {
+        let r = x_basis.cols();
+        // zero as many rows as there are degrees of freedom for, but leave a few tag bits over:
+        // each successful forgery then teaches us several equations instead of just one
+        let zero_rows = tag_bits
+            .saturating_sub(LEARN_BITS)
+            .max(1)
+            .min((n * 128 - 1) / r);
+
+        // T maps the bits of (d_1 .. d_n) to the bits of the first zero_rows rows of Ad * X.
+        // column (i, j) of T is d_i = x^j, and Ad * X_c is then x^j * X_c^(2^i) -- cheaper to get
+        // with field arithmetic than by multiplying matrices
+        let mut t_matrix = Gf2Matrix::zero(zero_rows * r, n * 128);
+        for c in 0..r {
+            let mut squared = Gf128::from_bits(&x_basis.column(c));
+            for i in 0..n {
+                squared = squared.square();
+                let mut product = squared;
+                for j in 0..128 {
+                    for a in 0..zero_rows {
+                        if (product.0 >> (127 - a)) & 1 == 1 {
+                            t_matrix.set(a * r + c, i * 128 + j, true);
+                        }
+                    }
+                    product = product.times_x();
+                }
+            }
+        }
+        // the length block's contribution has to be cancelled out by the d_i
+        let constant = length_difference
+            .multiplication_matrix()
+            .take_rows(zero_rows)
+            .mul(&x_basis);
+        let target: Vec<bool> = (0..zero_rows * r)
+            .map(|index| constant.get(index / r, index % r))
+            .collect();
+        let (particular, kernel) = t_matrix
+            .solve_all(&target)
+            .ok_or_else(|| anyhow!("no d zeroes {zero_rows} rows of Ad"))?;
+
+        // now throw forgeries at the receiver until one sticks
+        let d = loop {
+            let select: Vec<bool> = (0..kernel.rows()).map(|_| rand::random()).collect();
+            let bits: Vec<bool> = kernel
+                .sum_rows(&select)
+                .iter()
+                .zip(particular.iter())
+                .map(|(a, b)| a ^ b)
+                .collect();
+            let d: Vec<Gf128> = bits.chunks(128).map(Gf128::from_bits).collect();
+            if length_difference.is_zero() && d.iter().all(|d_i| d_i.is_zero()) {
+                // that's just the original message again
+                continue;
+            }
+            if receiver.accepts(&forge(message, &d, forged_blocks)) {
+                break d;
+            }
+        };
+
+        // it got through, so the first tag_bits rows of Ad * h are all zero
+        let ad = ad_matrix(&d, length_difference, &squarings);
+        k_matrix.append_rows(&ad.take_rows(tag_bits).without_zero_rows());
+        x_basis = k_matrix.kernel().transpose();
+    }
+    if x_basis.cols() == 0 {
+        return Err(anyhow!("learned inconsistent equations about h"));
+    }
+    Ok(Gf128::from_bits(&x_basis.column(0)))
+}
+
+/// with h in hand, sign whatever ciphertext we like under the captured message's nonce: the mask
+/// cancels out between the two tags, so we don't need the rest of it
+pub fn forge_with_authentication_key(h: Gf128, captured: &Message, ciphertext: &[u8]) -> Message {
+    let difference = gcm::ghash(h, &[], ciphertext) + gcm::ghash(h, &[], &captured.ciphertext);
+    let tag = captured
+        .tag
+        .iter()
+        .zip(difference.to_block())
+        .map(|(a, b)| a ^ b)
+        .collect();
+    Message {
+        nonce: captured.nonce.clone(),
+        ciphertext: ciphertext.to_vec(),
+        tag,
+    }
+}
+
+/// returns how many forgeries it sent
+pub fn attack() -> Result<usize> {
+    attack_with(TAG_SIZE, N)
+}
+
+/// the whole thing with a tag_size byte tag and a 2^n block captured message
+pub fn attack_with(tag_size: usize, n: usize) -> Result<usize> {
+    let mut receiver = Receiver::new(tag_size);
+    // a nice long message, 2^n blocks
+    let plaintext = b"the dog is good.".repeat(1 << n);
+    let captured = receiver.send(&plaintext)?;
+
+    let h = recover_authentication_key(&mut receiver, &captured, n, false)?;
+    assert_eq!(h, receiver.authentication_key());
+
+    let forgery = forge_with_authentication_key(h, &captured, b"the cat is better, actually");
+    assert!(receiver.accepts(&forgery));
+    Ok(receiver.queries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_byte_tag() {
+        attack_with(1, 9).unwrap();
+    }
+
+    // the challenge's own numbers. very slow
+    #[test]
+    #[ignore]
+    fn test_32_bit_tag() {
+        attack_with(4, 17).unwrap();
+    }
+}
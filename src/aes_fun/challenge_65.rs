@@ -0,0 +1,35 @@
+// Truncated-MAC GCM Revisited: Improving the Key-Recovery Attack via Ciphertext Length Extension
+// The attack in challenge 64 needs a captured message at least 2^n blocks long, because the
+// forgeries only ever flip bits in blocks that are already there. That's a lot to ask: most
+// messages on the wire are a handful of blocks.
+//
+// But nothing stops us from sending the receiver a *longer* ciphertext than the one we captured.
+// Prepend zero blocks: the original blocks keep their powers of h, the new blocks at powers of two
+// carry our d_i, and the only other thing that changes is the length block. That's just one more
+// known constant, L' + L, multiplying h -- so it joins Ad as M_(L' + L):
+//
+//  Ad = M_(L' + L) + sum of M_(d_i) * Ms^i
+//
+// Zeroing rows of Ad is no longer a homogeneous system, but it's still linear: solve T * d = b for
+// one particular solution and add random elements of the kernel of T to it.
+//
+// Now any captured message will do, and we pick n ourselves.
+
+use crate::aes_fun::challenge_64::{
+    forge_with_authentication_key, recover_authentication_key, Receiver, N, TAG_SIZE,
+};
+use anyhow::Result;
+
+/// returns how many forgeries it sent
+pub fn attack() -> Result<usize> {
+    let mut receiver = Receiver::new(TAG_SIZE);
+    // two blocks. challenge 64 would need 2^N of them
+    let captured = receiver.send(b"hi mom, the dog says hi as well.")?;
+
+    let h = recover_authentication_key(&mut receiver, &captured, N, true)?;
+    assert_eq!(h, receiver.authentication_key());
+
+    let forgery = forge_with_authentication_key(h, &captured, b"hi mom, please wire me $500");
+    assert!(receiver.accepts(&forgery));
+    Ok(receiver.queries)
+}
@@ -1,8 +1,6 @@
 use super::Key;
-use crate::cryptopal_util;
-use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
-use aes::Aes128;
-use anyhow::Result;
+use crate::aes_fun::block_cipher::{Aes, BlockCipher};
+use anyhow::{anyhow, Result};
 
 //The string:
 //
@@ -35,36 +33,284 @@ use anyhow::Result;
 //
 // Decrypt the string at the top of this function, then use your CTR function to encrypt and decrypt other things.
 
-/// returns new_iv, keystream
-fn keystream(key: Key, nonce: u64, start_iv: u64, bytes_to_produce: usize) -> (u64, Vec<u8>) {
-    // round up bytes_to_produce
-    let bytes_to_produce = (bytes_to_produce + 15) / 16 * 16;
-    let mut keystream = vec![0_u8; bytes_to_produce];
-    // how many blocks?
-    let blocks_to_produce = bytes_to_produce / 16;
-    let mut iv = start_iv;
-    let cipher = Aes128::new(GenericArray::from_slice(key.0));
-    let mut buf = [0_u8; 16];
-    for i in 0..blocks_to_produce {
-        // write nonce to buf
-        buf[0..8].copy_from_slice(&nonce.to_le_bytes());
-        // write little endian iv to buf
-        buf[8..].clone_from_slice(&iv.to_le_bytes());
-        println!("{buf:?}");
-        cipher.encrypt_block(GenericArray::from_mut_slice(&mut buf));
-        keystream[i * 16..(i + 1) * 16].copy_from_slice(&buf);
-        iv += 1;
-    }
-    (iv, keystream)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// how the counter blocks are laid out: the nonce, then a counter_width byte counter that starts
+/// at initial_counter and wraps around at 2^(8 * counter_width)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CtrParams {
+    pub nonce: Vec<u8>,
+    pub counter_width: usize,
+    pub endianness: Endianness,
+    pub initial_counter: u128,
+}
+
+impl CtrParams {
+    /// the challenge 18 layout: 64 bit little endian nonce, 64 bit little endian block count
+    pub fn cryptopals(nonce: u64) -> CtrParams {
+        CtrParams {
+            nonce: nonce.to_le_bytes().to_vec(),
+            counter_width: 8,
+            endianness: Endianness::Little,
+            initial_counter: 0,
+        }
+    }
+
+    /// a 96 bit nonce and a 32 bit big endian counter, like GCM and RFC 3686 (where the "nonce"
+    /// is the 32 bit nonce followed by the 64 bit IV)
+    pub fn big_endian_32(nonce: &[u8], initial_counter: u32) -> CtrParams {
+        CtrParams {
+            nonce: nonce.to_vec(),
+            counter_width: 4,
+            endianness: Endianness::Big,
+            initial_counter: initial_counter.into(),
+        }
+    }
+
+    /// the whole block is one big endian counter, as in NIST SP 800-38A
+    pub fn whole_block(initial_counter_block: &[u8]) -> CtrParams {
+        CtrParams {
+            nonce: vec![],
+            counter_width: initial_counter_block.len(),
+            endianness: Endianness::Big,
+            initial_counter: initial_counter_block
+                .iter()
+                .fold(0, |acc, &byte| acc << 8 | u128::from(byte)),
+        }
+    }
+
+    fn check<C: BlockCipher>(&self, cipher: &C) -> Result<()> {
+        if self.counter_width == 0 || self.counter_width > 16 {
+            return Err(anyhow!(
+                "CTR counter must be 1 to 16 bytes, got {}",
+                self.counter_width
+            ));
+        }
+        if self.nonce.len() + self.counter_width != cipher.block_size() {
+            return Err(anyhow!(
+                "a {} byte nonce and a {} byte counter don't make a {} byte block",
+                self.nonce.len(),
+                self.counter_width,
+                cipher.block_size()
+            ));
+        }
+        if self.counter_width < 16 && self.initial_counter >> (8 * self.counter_width) != 0 {
+            return Err(anyhow!(
+                "initial counter {} doesn't fit in {} bytes",
+                self.initial_counter,
+                self.counter_width
+            ));
+        }
+        Ok(())
+    }
+
+    fn counter_block(&self, block_index: u64) -> Vec<u8> {
+        let mut counter = self.initial_counter.wrapping_add(block_index.into());
+        if self.counter_width < 16 {
+            counter &= (1 << (8 * self.counter_width)) - 1;
+        }
+        let mut block = self.nonce.clone();
+        match self.endianness {
+            Endianness::Little => {
+                block.extend_from_slice(&counter.to_le_bytes()[..self.counter_width])
+            }
+            Endianness::Big => {
+                block.extend_from_slice(&counter.to_be_bytes()[16 - self.counter_width..])
+            }
+        }
+        block
+    }
+}
+
+/// CTR keystream that can start anywhere: seek to a byte offset and carry on from there, without
+/// generating everything before it
+pub struct CtrStream<C: BlockCipher> {
+    cipher: C,
+    params: CtrParams,
+    position: u64,
+    // the most recent keystream block and its index, so byte-at-a-time use doesn't re-encrypt
+    cached_block: Option<(u64, Vec<u8>)>,
+}
+
+impl<C: BlockCipher> CtrStream<C> {
+    pub fn new(cipher: C, params: CtrParams) -> Result<CtrStream<C>> {
+        params.check(&cipher)?;
+        Ok(CtrStream {
+            cipher,
+            params,
+            position: 0,
+            cached_block: None,
+        })
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn seek(&mut self, offset: u64) {
+        self.position = offset;
+    }
+
+    fn keystream_block(&mut self, block_index: u64) -> &[u8] {
+        if !matches!(&self.cached_block, Some((index, _)) if *index == block_index) {
+            let mut block = self.params.counter_block(block_index);
+            self.cipher.encrypt_block(&mut block);
+            self.cached_block = Some((block_index, block));
+        }
+        &self.cached_block.as_ref().unwrap().1
+    }
+
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        let block_size = self.cipher.block_size() as u64;
+        for byte in data.iter_mut() {
+            let (block_index, offset) = (self.position / block_size, self.position % block_size);
+            *byte ^= self.keystream_block(block_index)[offset as usize];
+            self.position += 1;
+        }
+    }
+
+    pub fn process(&mut self, input: &[u8]) -> Vec<u8> {
+        let mut output = input.to_vec();
+        self.apply_keystream(&mut output);
+        output
+    }
 }
 
 pub fn decrypt(ciphertext: &[u8], key: Key, nonce: u64) -> Result<Vec<u8>> {
-    let (_, keystream) = keystream(key, nonce, 0, ciphertext.len());
-    let plaintext = cryptopal_util::fixed_xor(ciphertext, &keystream);
-    Ok(plaintext)
+    encrypt(ciphertext, key, nonce)
+}
+
+pub fn encrypt(input: &[u8], key: Key, nonce: u64) -> Result<Vec<u8>> {
+    encrypt_with(&Aes::new(key)?, input, nonce)
+}
+
+/// CTR with the challenge 18 counter layout, which only fits a 16 byte block
+pub fn encrypt_with<C: BlockCipher>(cipher: &C, input: &[u8], nonce: u64) -> Result<Vec<u8>> {
+    encrypt_with_params(cipher, input, CtrParams::cryptopals(nonce))
 }
 
-pub fn encrypt(input: &[u8], key: Key, nonce: u64) -> Vec<u8> {
-    let (_, keystream) = keystream(key, nonce, 0, input.len());
-    cryptopal_util::fixed_xor(input, &keystream)
+pub fn decrypt_with<C: BlockCipher>(cipher: &C, ciphertext: &[u8], nonce: u64) -> Result<Vec<u8>> {
+    encrypt_with(cipher, ciphertext, nonce)
+}
+
+pub fn encrypt_with_params<C: BlockCipher>(
+    cipher: &C,
+    input: &[u8],
+    params: CtrParams,
+) -> Result<Vec<u8>> {
+    Ok(CtrStream::new(cipher, params)?.process(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::block_cipher::sp800_38a::*;
+
+    #[test]
+    fn test_sp800_38a_vectors() {
+        let cases = [
+            (
+                KEY_128,
+                "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff\
+                 5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee",
+            ),
+            (
+                KEY_192,
+                "1abc932417521ca24f2b0459fe7e6e0b090339ec0aa6faefd5ccc2c6f4ce8e94\
+                 1e36b26bd1ebc670d1bd1d665620abf74f78a7f6d29809585a97daec58c6b050",
+            ),
+            (
+                KEY_256,
+                "601ec313775789a5b7a7f504bbf3d228f443e3ca4d62b59aca84e990cacaf5c5\
+                 2b0930daa23de94ce87017ba2d84988ddfc9c58db67aada613c2dd08457941a6",
+            ),
+        ];
+        for (key, ciphertext) in cases {
+            let cipher = Aes::new(Key(&hex(key))).unwrap();
+            let params = CtrParams::whole_block(&hex(COUNTER_BLOCK));
+            assert_eq!(
+                encrypt_with_params(&cipher, &hex(PLAINTEXT), params.clone()).unwrap(),
+                hex(ciphertext)
+            );
+            // no padding in CTR, so any length works
+            assert_eq!(
+                encrypt_with_params(&cipher, &hex(ciphertext)[..37], params).unwrap(),
+                hex(PLAINTEXT)[..37]
+            );
+        }
+    }
+
+    #[test]
+    fn test_rfc_3686_vectors() {
+        // test vectors #1 and #2: the counter block is nonce || IV || 32 bit counter from 1
+        let cases = [
+            (
+                "ae6852f8121067cc4bf7a5765577f39e",
+                "00000030",
+                "0000000000000000",
+                "53696e676c6520626c6f636b206d7367",
+                "e4095d4fb7a7b3792d6175a3261311b8",
+            ),
+            (
+                "7e24067817fae0d743d6ce1f32539163",
+                "006cb6db",
+                "c0543b59da48d90b",
+                "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                "5104a106168a72d9790d41ee8edad388eb2e1efc46da57c8fce630df9141be28",
+            ),
+        ];
+        for (key, nonce, iv, plaintext, ciphertext) in cases {
+            let cipher = Aes::new(Key(&hex(key))).unwrap();
+            let mut nonce = hex(nonce);
+            nonce.extend(hex(iv));
+            let params = CtrParams::big_endian_32(&nonce, 1);
+            assert_eq!(
+                encrypt_with_params(&cipher, &hex(plaintext), params).unwrap(),
+                hex(ciphertext)
+            );
+        }
+    }
+
+    #[test]
+    fn test_seek() {
+        let cipher = Aes::new(Key(b"YELLOW SUBMARINE")).unwrap();
+        let plaintext = b"we all live in a yellow submarine, a yellow submarine";
+        let ciphertext = encrypt_with(&cipher, plaintext, 7).unwrap();
+        let mut stream = CtrStream::new(&cipher, CtrParams::cryptopals(7)).unwrap();
+        for offset in [0, 5, 16, 21, 40] {
+            stream.seek(offset as u64);
+            assert_eq!(stream.process(&ciphertext[offset..]), plaintext[offset..]);
+            assert_eq!(stream.position(), plaintext.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_counter_wraps() {
+        let cipher = Aes::new(Key(b"YELLOW SUBMARINE")).unwrap();
+        let params = CtrParams::big_endian_32(&[9; 12], u32::MAX);
+        let mut stream = CtrStream::new(&cipher, params).unwrap();
+        stream.seek(16);
+        let mut wrapped = [0u8; 16];
+        stream.apply_keystream(&mut wrapped);
+        let mut expected = [9; 16];
+        expected[12..].copy_from_slice(&[0; 4]);
+        cipher.encrypt_block(&mut expected);
+        assert_eq!(wrapped, expected);
+    }
+
+    #[test]
+    fn test_bad_sizes() {
+        assert!(encrypt(b"hello", Key(b"short key"), 0).is_err());
+        let cipher = Aes::new(Key(b"YELLOW SUBMARINE")).unwrap();
+        assert!(encrypt_with_params(&cipher, b"hello", CtrParams::whole_block(&[0; 8])).is_err());
+        let too_big = CtrParams {
+            initial_counter: 1 << 32,
+            ..CtrParams::big_endian_32(&[0; 12], 0)
+        };
+        assert!(encrypt_with_params(&cipher, b"hello", too_big).is_err());
+    }
 }
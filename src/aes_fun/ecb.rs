@@ -1,39 +1,41 @@
+use crate::aes_fun::block_cipher::{Aes, BlockCipher};
 use crate::aes_fun::Key;
 use crate::pkcs7::{pkcs7_pad, pkcs7_unpad};
-use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
-use aes::Aes128;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::collections::HashSet;
 
 pub fn decrypt(ciphertext: &[u8], key: Key) -> Result<Vec<u8>> {
-    let mut plaintext = Vec::new();
-    let block_size = 16;
-    let key = GenericArray::from_slice(key.0);
-    let cipher = Aes128::new(key);
-    let mut block_spot = [0u8; 16];
-    for chunk in ciphertext.chunks(block_size) {
-        block_spot.clone_from_slice(chunk);
-        let block = GenericArray::from_mut_slice(&mut block_spot);
+    decrypt_with(&Aes::new(key)?, ciphertext)
+}
+
+pub fn encrypt(plaintext: &[u8], key: Key) -> Result<Vec<u8>> {
+    Ok(encrypt_with(&Aes::new(key)?, plaintext))
+}
+
+pub fn decrypt_with<C: BlockCipher>(cipher: &C, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    pkcs7_unpad(&decrypt_no_unpad_with(cipher, ciphertext)?)
+}
+
+pub fn decrypt_no_unpad_with<C: BlockCipher>(cipher: &C, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let block_size = cipher.block_size();
+    if !ciphertext.len().is_multiple_of(block_size) {
+        return Err(anyhow!(
+            "ECB ciphertext must be a whole number of {block_size} byte blocks"
+        ));
+    }
+    let mut plaintext = ciphertext.to_vec();
+    for block in plaintext.chunks_mut(block_size) {
         cipher.decrypt_block(block);
-        plaintext.extend(block.as_slice());
     }
-    Ok(pkcs7_unpad(plaintext.as_slice())?.to_vec())
-}
-
-pub fn encrypt(ciphertext: &[u8], key: Key) -> Vec<u8> {
-    let ciphertext = pkcs7_pad(ciphertext, 16);
-    let mut plaintext = Vec::new();
-    let block_size = 16;
-    let key = GenericArray::from_slice(key.0);
-    let cipher = Aes128::new(key);
-    let mut block_spot = [0u8; 16];
-    for chunk in ciphertext.chunks(block_size) {
-        block_spot.clone_from_slice(chunk);
-        let block = GenericArray::from_mut_slice(&mut block_spot);
+    Ok(plaintext)
+}
+
+pub fn encrypt_with<C: BlockCipher>(cipher: &C, plaintext: &[u8]) -> Vec<u8> {
+    let mut ciphertext = pkcs7_pad(plaintext, cipher.block_size());
+    for block in ciphertext.chunks_mut(cipher.block_size()) {
         cipher.encrypt_block(block);
-        plaintext.extend(block.as_slice());
     }
-    plaintext
+    ciphertext
 }
 
 pub fn is_ecb(ciphertext: &[u8]) -> bool {
@@ -51,3 +53,50 @@ pub fn find_aes_ecb_ciphertexts(ciphertexts: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
         .cloned()
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::block_cipher::sp800_38a::*;
+
+    #[test]
+    fn test_sp800_38a_vectors() {
+        let cases = [
+            (
+                KEY_128,
+                "3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf\
+                 43b1cd7f598ece23881b00e3ed0306887b0c785e27e8ad3f8223207104725dd4",
+            ),
+            (
+                KEY_192,
+                "bd334f1d6e45f25ff712a214571fa5cc974104846d0ad3ad7734ecb3ecee4eef\
+                 ef7afd2270e2e60adce0ba2face6444e9a4b41ba738d6c72fb16691603c18e0e",
+            ),
+            (
+                KEY_256,
+                "f3eed1bdb5d2a03c064b5a7e3db181f8591ccb10d410ed26dc5ba74a31362870\
+                 b6ed21b99ca6f4f9f153e7b1beafed1d23304b7a39f9f3ff067d8d8f9e24ecc7",
+            ),
+        ];
+        for (key, ciphertext) in cases {
+            let cipher = Aes::new(Key(&hex(key))).unwrap();
+            // the vectors are unpadded, so leave off our padding block
+            let my_ciphertext = encrypt_with(&cipher, &hex(PLAINTEXT));
+            assert_eq!(my_ciphertext[..64], hex(ciphertext));
+            assert_eq!(
+                decrypt_no_unpad_with(&cipher, &hex(ciphertext)).unwrap(),
+                hex(PLAINTEXT)
+            );
+            assert_eq!(
+                decrypt_with(&cipher, &my_ciphertext).unwrap(),
+                hex(PLAINTEXT)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_sizes() {
+        assert!(encrypt(b"hello", Key(b"short key")).is_err());
+        assert!(decrypt(&[0; 17], Key(b"YELLOW SUBMARINE")).is_err());
+    }
+}
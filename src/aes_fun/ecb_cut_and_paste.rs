@@ -0,0 +1,199 @@
+// ECB cut-and-paste (challenge 13), for any token format we know the layout of.
+//
+// ECB encrypts every block on its own, so a forged token is just the right ciphertext blocks in
+// the right order. For each block of the (padded) token we want, there are two places to get it:
+//
+//  - the oracle's own output, when our input is the right length to line up a block of the
+//    template that already says what we want (like "&uid=10&role=" ending on a block boundary)
+//  - our input, when we pad out the template's first block and then send the block itself. This
+//    only works if the oracle lets every byte of it through untouched
+//
+// We don't get told which bytes the oracle eats or escapes, but we can tell from the length of
+// what comes back: right before the padding spills into a new block, an escaped byte spills it,
+// and right after, an eaten byte un-spills it.
+//
+// The token we want usually has our input in it too, so we try every length of input up to a
+// block until all of its blocks can be found somewhere.
+
+use super::block_cipher::detect_block_size;
+use crate::pkcs7::pkcs7_pad;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// the oracle encrypts prefix || (our input, maybe quoted) || suffix
+pub struct Template<'a> {
+    pub prefix: &'a [u8],
+    pub suffix: &'a [u8],
+}
+
+pub struct CutAndPaste<'a, F: Fn(&[u8]) -> Result<Vec<u8>>> {
+    oracle: &'a F,
+    template: Template<'a>,
+    block_size: usize,
+    // the shortest input that makes the ciphertext grow
+    grow_at: usize,
+    empty_length: usize,
+    safe: HashMap<u8, bool>,
+    // ciphertexts for FILLER * n
+    natural: HashMap<usize, Vec<u8>>,
+}
+
+const FILLER: u8 = b'A';
+
+impl<'a, F: Fn(&[u8]) -> Result<Vec<u8>>> CutAndPaste<'a, F> {
+    pub fn new(oracle: &'a F, template: Template<'a>) -> Result<CutAndPaste<'a, F>> {
+        let empty_length = oracle(&[])?.len();
+        let (block_size, grow_at) = detect_block_size(oracle, FILLER)?;
+        let mut cut_and_paste = CutAndPaste {
+            oracle,
+            template,
+            block_size,
+            grow_at,
+            empty_length,
+            safe: HashMap::new(),
+            natural: HashMap::new(),
+        };
+        if !cut_and_paste.is_safe(FILLER) {
+            return Err(anyhow!("the oracle doesn't let our filler through"));
+        }
+        Ok(cut_and_paste)
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// whether the oracle passes this byte through as it is
+    pub fn is_safe(&mut self, byte: u8) -> bool {
+        if let Some(&safe) = self.safe.get(&byte) {
+            return safe;
+        }
+        let oracle = self.oracle;
+        // a block further along than grow_at, so there's always room for the byte
+        let mut just_fits = vec![FILLER; self.grow_at - 1 + self.block_size];
+        *just_fits.last_mut().unwrap() = byte;
+        let mut just_spills = vec![FILLER; self.grow_at + self.block_size];
+        *just_spills.last_mut().unwrap() = byte;
+        let safe = match (oracle(&just_fits), oracle(&just_spills)) {
+            (Ok(fits), Ok(spills)) => {
+                fits.len() == self.empty_length + self.block_size
+                    && spills.len() == self.empty_length + 2 * self.block_size
+            }
+            _ => false,
+        };
+        self.safe.insert(byte, safe);
+        safe
+    }
+
+    fn natural_ciphertext(&mut self, filler_length: usize) -> Result<&[u8]> {
+        if !self.natural.contains_key(&filler_length) {
+            let ciphertext = (self.oracle)(&vec![FILLER; filler_length])?;
+            self.natural.insert(filler_length, ciphertext);
+        }
+        Ok(&self.natural[&filler_length])
+    }
+
+    /// a ciphertext block that decrypts to block, if we can get one
+    pub fn find_block(&mut self, block: &[u8]) -> Result<Option<Vec<u8>>> {
+        let block_size = self.block_size;
+        // somewhere in the template, lined up by some amount of filler
+        for filler_length in 0..3 * block_size {
+            let mut plaintext = self.template.prefix.to_vec();
+            plaintext.extend(vec![FILLER; filler_length]);
+            plaintext.extend_from_slice(self.template.suffix);
+            let plaintext = pkcs7_pad(&plaintext, block_size);
+            if let Some(i) = plaintext.chunks(block_size).position(|b| b == block) {
+                let ciphertext = self.natural_ciphertext(filler_length)?;
+                return Ok(ciphertext
+                    .get(i * block_size..(i + 1) * block_size)
+                    .map(|b| b.to_vec()));
+            }
+        }
+        // or sent in as our input, on its own block
+        if block.iter().all(|&byte| self.is_safe(byte)) {
+            let prefix_length = self.template.prefix.len();
+            let alignment = (block_size - prefix_length % block_size) % block_size;
+            let mut input = vec![FILLER; alignment];
+            input.extend_from_slice(block);
+            let start = prefix_length + alignment;
+            return Ok((self.oracle)(&input)?
+                .get(start..start + block_size)
+                .map(|b| b.to_vec()));
+        }
+        Ok(None)
+    }
+
+    /// the ciphertext of plaintext, built out of blocks the oracle gave us, if we can get them all
+    pub fn splice(&mut self, plaintext: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut forged = vec![];
+        for block in pkcs7_pad(plaintext, self.block_size).chunks(self.block_size) {
+            match self.find_block(block)? {
+                Some(ciphertext_block) => forged.extend(ciphertext_block),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(forged))
+    }
+
+    /// target says what token we want, given what we used as our input. tries every length of
+    /// input up to a block until one of them can be spliced together
+    pub fn forge<T: Fn(&[u8]) -> Vec<u8>>(&mut self, target: T) -> Result<Vec<u8>> {
+        for filler_length in 0..self.block_size {
+            if let Some(forged) = self.splice(&target(&vec![FILLER; filler_length]))? {
+                return Ok(forged);
+            }
+        }
+        Err(anyhow!("couldn't line up the blocks we need"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::{ecb, Key};
+    use crate::kv;
+
+    const KEY: &[u8] = b"YELLOW SUBMARINE";
+
+    fn check(oracle: &dyn Fn(&[u8]) -> Result<Vec<u8>>) {
+        let template = Template {
+            prefix: b"email=",
+            suffix: b"&uid=10&role=user",
+        };
+        let mut cut_and_paste = CutAndPaste::new(&oracle, template).unwrap();
+        assert_eq!(cut_and_paste.block_size(), 16);
+        assert!(!cut_and_paste.is_safe(b'&'));
+        assert!(!cut_and_paste.is_safe(b'='));
+        let forged = cut_and_paste
+            .forge(|email| {
+                let email = String::from_utf8(email.to_vec()).unwrap();
+                kv::encode(&[("email", &email), ("uid", "10"), ("role", "admin")]).into_bytes()
+            })
+            .unwrap();
+        let token = String::from_utf8(ecb::decrypt(&forged, Key(KEY)).unwrap()).unwrap();
+        assert_eq!(kv::parse_map(&token).unwrap()["role"], "admin");
+    }
+
+    #[test]
+    fn test_eating_oracle() {
+        check(&|email: &[u8]| {
+            let email = String::from_utf8(email.to_vec())?.replace(['&', '='], "");
+            let token = format!("email={email}&uid=10&role=user");
+            ecb::encrypt(token.as_bytes(), Key(KEY))
+        });
+    }
+
+    #[test]
+    fn test_escaping_oracle() {
+        // quoting properly keeps "&role=admin" out, but not pasted blocks
+        let oracle = |email: &[u8]| {
+            let email = String::from_utf8(email.to_vec())?;
+            let token = kv::encode(&[("email", &email), ("uid", "10"), ("role", "user")]);
+            ecb::encrypt(token.as_bytes(), Key(KEY))
+        };
+        let injected = ecb::decrypt(&oracle(b"foo@bar.com&role=admin").unwrap(), Key(KEY)).unwrap();
+        let injected = kv::parse_map(&String::from_utf8(injected).unwrap()).unwrap();
+        assert_eq!(injected["role"], "user");
+        check(&oracle);
+    }
+}
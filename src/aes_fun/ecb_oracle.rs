@@ -0,0 +1,223 @@
+// Byte-at-a-time ECB decryption (challenges 12 and 14), pulled out so it works against anything
+// that will encrypt prefix || our input || secret under ECB for us.
+//
+// Everything we need to know comes from ciphertext lengths and repeated blocks, so none of it
+// cares what the prefix says, only how long it is. That means a prefix that's different random
+// junk every call is fine, as long as its length doesn't change.
+//
+//  - block size: keep adding bytes until the ciphertext grows. It grows by a block.
+//  - ECB: three blocks' worth of the same byte has at least two whole blocks of it, wherever it
+//    starts, and ECB turns those into two identical ciphertext blocks.
+//  - prefix length: pad our input with p bytes then two blocks of a marker byte. The smallest p
+//    that gives two identical ciphertext blocks in a row lines the marker up with a block
+//    boundary, and which blocks repeat says where. We ask with two different markers, since the
+//    prefix could end (or the secret start) with bytes that look like one of them.
+//  - secret length: the ciphertext length, minus the prefix, minus however many bytes it took to
+//    make the padding spill into a new block.
+//  - the secret: push it so the byte we want is the last byte of a block whose other bytes we
+//    already know, then compare that block with all 256 ways it could have gone.
+
+use super::block_cipher;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// anything that ECB-encrypts (maybe some prefix) || input || secret
+pub trait EcbOracle {
+    fn encrypt(&self, input: &[u8]) -> Vec<u8>;
+}
+
+/// what the attack worked out about the oracle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcbOracleReport {
+    pub block_size: usize,
+    pub prefix_length: usize,
+    pub secret_length: usize,
+    pub secret: Vec<u8>,
+    pub queries: usize,
+}
+
+pub struct EcbOracleAttacker<'a, O: EcbOracle + ?Sized> {
+    oracle: &'a O,
+    queries: usize,
+}
+
+const FILLER: u8 = b'A';
+const MARKERS: [u8; 2] = [b'X', b'Y'];
+
+impl<'a, O: EcbOracle + ?Sized> EcbOracleAttacker<'a, O> {
+    pub fn new(oracle: &'a O) -> EcbOracleAttacker<'a, O> {
+        EcbOracleAttacker { oracle, queries: 0 }
+    }
+
+    /// how many times we've asked the oracle so far
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    fn ask(&mut self, input: &[u8]) -> Vec<u8> {
+        self.queries += 1;
+        self.oracle.encrypt(input)
+    }
+
+    /// returns (block size, how many bytes of input it took to grow the ciphertext)
+    pub fn detect_block_size(&mut self) -> Result<(usize, usize)> {
+        block_cipher::detect_block_size(|input| Ok(self.ask(input)), FILLER)
+    }
+
+    pub fn detect_ecb(&mut self, block_size: usize) -> bool {
+        let ciphertext = self.ask(&vec![FILLER; 3 * block_size]);
+        first_repeated_block(&ciphertext, block_size).is_some()
+    }
+
+    pub fn detect_prefix_length(&mut self, block_size: usize) -> Result<usize> {
+        for padding in 0..block_size {
+            let mut repeats = vec![];
+            for marker in MARKERS {
+                let mut input = vec![FILLER; padding];
+                input.extend(vec![marker; 2 * block_size]);
+                repeats.push(first_repeated_block(&self.ask(&input), block_size));
+            }
+            if let [Some(a), Some(b)] = repeats[..] {
+                if a == b && a * block_size >= padding {
+                    return Ok(a * block_size - padding);
+                }
+            }
+        }
+        Err(anyhow!("couldn't line our input up with a block boundary"))
+    }
+
+    /// the byte at a time part, once we know the layout
+    pub fn recover_secret(
+        &mut self,
+        block_size: usize,
+        prefix_length: usize,
+        secret_length: usize,
+    ) -> Result<Vec<u8>> {
+        // this many bytes finishes off the prefix's last block, so our blocks start at base
+        let alignment = (block_size - prefix_length % block_size) % block_size;
+        let base = prefix_length + alignment;
+        let mut secret: Vec<u8> = Vec::with_capacity(secret_length);
+        while secret.len() < secret_length {
+            let offset = block_size - 1 - secret.len() % block_size;
+            let mut input = vec![FILLER; alignment + offset];
+            let ciphertext = self.ask(&input);
+            let target_start = base + secret.len() - secret.len() % block_size;
+            let target = ciphertext
+                .get(target_start..target_start + block_size)
+                .ok_or_else(|| anyhow!("the ciphertext was shorter than it should be"))?;
+
+            // what comes before the byte we want: the last block_size - 1 bytes of our offset
+            // bytes and the secret so far
+            input.truncate(offset);
+            input.extend_from_slice(&secret);
+            let known = input[input.len() - (block_size - 1)..].to_vec();
+
+            // all 256 guesses at once, a block each
+            let mut dictionary_input = vec![FILLER; alignment];
+            for guess in 0..=255u8 {
+                dictionary_input.extend_from_slice(&known);
+                dictionary_input.push(guess);
+            }
+            let dictionary_ciphertext = self.ask(&dictionary_input);
+            let dictionary = (0..=255u8)
+                .map(|guess| {
+                    let start = base + guess as usize * block_size;
+                    (&dictionary_ciphertext[start..start + block_size], guess)
+                })
+                .collect::<HashMap<&[u8], u8>>();
+            let byte = dictionary
+                .get(target)
+                .ok_or_else(|| anyhow!("no guess matched byte {} of the secret", secret.len()))?;
+            secret.push(*byte);
+        }
+        Ok(secret)
+    }
+
+    pub fn attack(&mut self) -> Result<EcbOracleReport> {
+        let (block_size, bytes_to_grow) = self.detect_block_size()?;
+        if !self.detect_ecb(block_size) {
+            return Err(anyhow!("the oracle doesn't look like it's using ECB"));
+        }
+        let prefix_length = self.detect_prefix_length(block_size)?;
+        // with no input, prefix + secret + bytes_to_grow fills the ciphertext exactly
+        let initial_length = self.ask(&[]).len();
+        let secret_length = initial_length
+            .checked_sub(prefix_length + bytes_to_grow)
+            .ok_or_else(|| anyhow!("the prefix is longer than the whole ciphertext"))?;
+        let secret = self.recover_secret(block_size, prefix_length, secret_length)?;
+        Ok(EcbOracleReport {
+            block_size,
+            prefix_length,
+            secret_length,
+            secret,
+            queries: self.queries,
+        })
+    }
+}
+
+/// index of the first block that's the same as the block after it
+fn first_repeated_block(ciphertext: &[u8], block_size: usize) -> Option<usize> {
+    let blocks = ciphertext.chunks_exact(block_size).collect::<Vec<_>>();
+    blocks.windows(2).position(|pair| pair[0] == pair[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::block_cipher::{Aes, BlockCipher, ToyCipher};
+    use crate::aes_fun::{cbc, ecb, Iv, Key};
+    use rand::Rng;
+
+    const SECRET: &[u8] = b"the secret ingredient is more secrets";
+
+    // a prefix of fresh random bytes every call, always the same length
+    struct Server<C: BlockCipher> {
+        cipher: C,
+        prefix_length: usize,
+    }
+
+    impl<C: BlockCipher> EcbOracle for Server<C> {
+        fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+            let mut plaintext = vec![0u8; self.prefix_length];
+            rand::thread_rng().fill(plaintext.as_mut_slice());
+            plaintext.extend_from_slice(input);
+            plaintext.extend_from_slice(SECRET);
+            ecb::encrypt_with(&self.cipher, &plaintext)
+        }
+    }
+
+    struct CbcServer;
+
+    impl EcbOracle for CbcServer {
+        fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+            cbc::encrypt(input, Key(b"YELLOW SUBMARINE"), Iv(&[0; 16])).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_attack() {
+        for prefix_length in [0, 1, 7, 16, 21, 40] {
+            let aes_server = Server {
+                cipher: Aes::new(Key(b"YELLOW SUBMARINE")).unwrap(),
+                prefix_length,
+            };
+            let toy_server = Server {
+                cipher: ToyCipher(0x5a),
+                prefix_length,
+            };
+            let servers: [(&dyn EcbOracle, usize); 2] = [(&aes_server, 16), (&toy_server, 8)];
+            for (server, block_size) in servers {
+                let report = EcbOracleAttacker::new(server).attack().unwrap();
+                assert_eq!(report.block_size, block_size);
+                assert_eq!(report.prefix_length, prefix_length);
+                assert_eq!(report.secret_length, SECRET.len());
+                assert_eq!(report.secret, SECRET);
+            }
+        }
+    }
+
+    #[test]
+    fn test_not_ecb() {
+        assert!(EcbOracleAttacker::new(&CbcServer).attack().is_err());
+    }
+}
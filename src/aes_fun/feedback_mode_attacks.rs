@@ -0,0 +1,127 @@
+// Challenge 26, again, for OFB and CFB.
+//
+// OFB is a stream cipher just like CTR, so everything that went wrong for CTR goes wrong here too:
+// flip a ciphertext bit and the same plaintext bit flips, and reuse an IV and you've reused the
+// whole keystream.
+//
+// CFB-128 is halfway to CBC. A flipped ciphertext bit flips the same plaintext bit, and then
+// scrambles the whole next block while it sits in the shift register. So, like challenge 16, we
+// give up a block of our own input to the garbage. Reusing an IV only reuses the first block of
+// keystream -- after that the ciphertexts differ and so do the registers -- but the first block is
+// plenty if you know what it says in one of the messages.
+//
+// CFB-8 scrambles the next 16 bytes after every flip, and ";admin=true;" needs three flips within
+// 12 bytes of each other, so it's left out of the bit flipping here: without a decryption oracle to
+// tell us what the garbage decrypted to, there's nowhere to hide it.
+
+use crate::aes_fun::cfb::{self, SegmentSize};
+use crate::aes_fun::{ofb, Iv, Key};
+use crate::cryptopal_util;
+use crate::random_things::{sixteen_random_bytes, MY_RANDOM_IV, MY_RANDOM_KEY};
+use anyhow::{anyhow, Result};
+
+const PREFIX: &str = "comment1=cooking%20MCs;userdata=";
+const SUFFIX: &str = ";comment2=%20like%20a%20pound%20of%20bacon";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Ofb,
+    Cfb128,
+}
+
+fn quote(input_string: &str) -> Vec<u8> {
+    let quoted_out = input_string.replace(';', "%3B").replace('=', "%3D");
+    format!("{PREFIX}{quoted_out}{SUFFIX}").into_bytes()
+}
+
+/// returns iv || ciphertext, under a fresh random IV
+fn oracle(input_string: &str, mode: Mode) -> Result<Vec<u8>> {
+    let iv = sixteen_random_bytes();
+    let key = Key(&MY_RANDOM_KEY);
+    let mut output = iv.clone();
+    output.extend(match mode {
+        Mode::Ofb => ofb::encrypt(&quote(input_string), key, Iv(&iv))?,
+        Mode::Cfb128 => cfb::encrypt(&quote(input_string), key, Iv(&iv), SegmentSize::Cfb128)?,
+    });
+    Ok(output)
+}
+
+fn target(input_bytes: &[u8], mode: Mode) -> Result<bool> {
+    if input_bytes.len() < 16 {
+        return Err(anyhow!(
+            "the ciphertext is too short to have an IV on the front"
+        ));
+    }
+    let (iv, ciphertext) = input_bytes.split_at(16);
+    let key = Key(&MY_RANDOM_KEY);
+    let decrypted_bytes = match mode {
+        Mode::Ofb => ofb::decrypt(ciphertext, key, Iv(iv))?,
+        Mode::Cfb128 => cfb::decrypt(ciphertext, key, Iv(iv), SegmentSize::Cfb128)?,
+    };
+    // the scrambled block probably isn't valid utf8, so look for the bytes
+    Ok(decrypted_bytes
+        .windows(12)
+        .any(|window| window == b";admin=true;"))
+}
+
+pub fn bitflipping_attack(mode: Mode) -> Result<bool> {
+    // the prefix is exactly two blocks, so our input starts a block. in CFB that first block of
+    // ours gets the edit and the second one gets scrambled
+    let input = "A".repeat(32);
+    let mut encrypted = oracle(&input, mode)?;
+    let jokes_and_tricks = cryptopal_util::fixed_xor(b";admin=true;", b"AAAAAAAAAAAA");
+    // skip the iv
+    let start = 16 + PREFIX.len();
+    for (i, byte) in jokes_and_tricks.iter().enumerate() {
+        encrypted[start + i] ^= byte;
+    }
+    target(&encrypted, mode)
+}
+
+/// everything gets encrypted under the same IV. oops
+fn fixed_iv_oracle(plaintext: &[u8], mode: Mode) -> Result<Vec<u8>> {
+    let key = Key(&MY_RANDOM_KEY);
+    match mode {
+        Mode::Ofb => ofb::encrypt(plaintext, key, Iv(&MY_RANDOM_IV)),
+        Mode::Cfb128 => cfb::encrypt(plaintext, key, Iv(&MY_RANDOM_IV), SegmentSize::Cfb128),
+    }
+}
+
+/// with one known plaintext under a reused IV, decrypt as much of another message as the mode
+/// gives away: all of it for OFB, the first block for CFB-128
+pub fn keystream_reuse_attack(mode: Mode) -> Result<Vec<u8>> {
+    let known_plaintext = "A".repeat(64).into_bytes();
+    let known_ciphertext = fixed_iv_oracle(&known_plaintext, mode)?;
+    let secret_ciphertext = fixed_iv_oracle(b"the eagle lands at midnight, bring snacks", mode)?;
+    let keystream = cryptopal_util::fixed_xor(&known_plaintext, &known_ciphertext);
+    let recovered = cryptopal_util::fixed_xor(&secret_ciphertext, &keystream);
+    Ok(match mode {
+        Mode::Ofb => recovered,
+        Mode::Cfb128 => recovered[..16].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitflipping() {
+        assert!(bitflipping_attack(Mode::Ofb).unwrap());
+        assert!(bitflipping_attack(Mode::Cfb128).unwrap());
+        assert!(target(&[0; 15], Mode::Ofb).is_err());
+        assert!(!target(&[0; 16], Mode::Cfb128).unwrap());
+    }
+
+    #[test]
+    fn test_keystream_reuse() {
+        assert_eq!(
+            keystream_reuse_attack(Mode::Ofb).unwrap(),
+            b"the eagle lands at midnight, bring snacks"
+        );
+        assert_eq!(
+            keystream_reuse_attack(Mode::Cfb128).unwrap(),
+            b"the eagle lands "
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Breaking CTR with a fixed nonce (challenges 19 and 20): every message is xored with the same
+// keystream, so it's a many-time pad. Column j of the keystream is a single byte xor over the jth
+// byte of every message long enough to have one.
+//
+// First pass: score every column on its own, as single byte xor with a unigram model. With lots of
+// messages that gets almost every column. It gets it wrong where there aren't many samples (the
+// ends of the longest messages), or where the column isn't typical English (the first byte of each
+// line is a capital).
+//
+// Second pass: go back over the columns, least sure first, and score each guess with its
+// neighbours in every message as context, using the corpus bigram and trigram models. A fixed column
+// changes the context for the columns next to it, so repeat until nothing changes.
+//
+// The confidence of a keystream byte is how much of the probability (softmax over the 256 scores)
+// the best guess gets.
+
+use crate::englishness::scorer::{Scorer, BIGRAMS, TRIGRAMS, UNIGRAMS};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeystreamByte {
+    pub value: u8,
+    /// between 0 and 1
+    pub confidence: f64,
+    /// how many ciphertexts were long enough to reach this byte
+    pub samples: usize,
+}
+
+const MAX_PASSES: usize = 10;
+// the corpus is small, so its n-gram probabilities are far surer of themselves than they should
+// be, and the five n-grams around a byte are hardly five separate pieces of evidence. without
+// cooling them down, a column with two samples comes out 99% sure of the wrong byte
+const CONTEXT_TEMPERATURE: f64 = 15.0;
+
+fn best_of(scores: &[f64]) -> (u8, f64) {
+    let (best, best_score) = scores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .unwrap();
+    let total: f64 = scores.iter().map(|s| (s - best_score).exp()).sum();
+    (best as u8, 1.0 / total)
+}
+
+fn unigram_scores(ciphertexts: &[Vec<u8>], j: usize) -> Vec<f64> {
+    (0..=255u8)
+        .map(|k| {
+            ciphertexts
+                .iter()
+                .filter_map(|ciphertext| ciphertext.get(j))
+                .map(|c| UNIGRAMS.log_probability(&[c ^ k]))
+                .sum()
+        })
+        .collect()
+}
+
+fn context_scores(ciphertexts: &[Vec<u8>], keystream: &[KeystreamByte], j: usize) -> Vec<f64> {
+    let mut scores = vec![0.0; 256];
+    for ciphertext in ciphertexts.iter().filter(|c| c.len() > j) {
+        // what we think the plaintext is around column j, with newlines off the ends
+        let plain = |offset: isize| -> u8 {
+            let i = j as isize + offset;
+            if i < 0 || i as usize >= ciphertext.len() {
+                b'\n'
+            } else {
+                ciphertext[i as usize] ^ keystream[i as usize].value
+            }
+        };
+        let (before2, before, after, after2) = (plain(-2), plain(-1), plain(1), plain(2));
+        for (k, score) in scores.iter_mut().enumerate() {
+            // every bigram and trigram with p in it
+            let window = [before2, before, ciphertext[j] ^ k as u8, after, after2];
+            *score +=
+                (BIGRAMS.score(&window[1..4]) + TRIGRAMS.score(&window)) / CONTEXT_TEMPERATURE;
+        }
+    }
+    scores
+}
+
+/// the keystream that makes the ciphertexts look most like English, as far as the longest one
+pub fn break_fixed_nonce(ciphertexts: &[Vec<u8>]) -> Vec<KeystreamByte> {
+    let length = ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0);
+    let mut keystream = (0..length)
+        .map(|j| {
+            let (value, confidence) = best_of(&unigram_scores(ciphertexts, j));
+            KeystreamByte {
+                value,
+                confidence,
+                samples: ciphertexts.iter().filter(|c| c.len() > j).count(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // least sure first, so the others get the best context we can give them
+    let mut order = (0..length).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| keystream[a].confidence.total_cmp(&keystream[b].confidence));
+    for _ in 0..MAX_PASSES {
+        let mut changed = false;
+        for &j in order.iter() {
+            let (value, confidence) = best_of(&context_scores(ciphertexts, &keystream, j));
+            changed |= value != keystream[j].value;
+            keystream[j].value = value;
+            keystream[j].confidence = confidence;
+        }
+        if !changed {
+            break;
+        }
+    }
+    keystream
+}
+
+pub fn decrypt_all(ciphertexts: &[Vec<u8>], keystream: &[KeystreamByte]) -> Vec<Vec<u8>> {
+    ciphertexts
+        .iter()
+        .map(|ciphertext| {
+            ciphertext
+                .iter()
+                .zip(keystream)
+                .map(|(c, k)| c ^ k.value)
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::challenge_19_and_20::{encrypt_all, read_plaintexts};
+    use crate::cryptopal_util;
+
+    #[test]
+    fn test_data_19_and_20() {
+        for filename in ["./data/19.txt", "./data/20.txt"] {
+            let plaintexts = read_plaintexts(filename).unwrap();
+            let ciphertexts = encrypt_all(&plaintexts).unwrap();
+            let keystream = break_fixed_nonce(&ciphertexts);
+            let real_keystream = ciphertexts
+                .iter()
+                .zip(&plaintexts)
+                .max_by_key(|(c, _)| c.len())
+                .map(|(c, p)| cryptopal_util::fixed_xor(c, p))
+                .unwrap();
+            assert_eq!(keystream.len(), real_keystream.len());
+            for (guess, real) in keystream.iter().zip(real_keystream) {
+                // a handful of samples is enough. below that it's a guess, and it says so
+                if guess.samples >= 5 {
+                    assert_eq!(guess.value, real);
+                }
+                if guess.value != real {
+                    assert!(guess.confidence < 0.9);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use super::Key;
+use crate::aes_fun::block_cipher::{Aes, BlockCipher};
+use crate::aes_fun::ctr::{CtrParams, CtrStream};
+use crate::aes_fun::gf128::Gf128;
+use anyhow::{anyhow, Result};
+
+// AES-GCM, per NIST SP 800-38D.
+//
+// GCM is CTR mode with a 32 bit big endian counter in the last four bytes of the counter block,
+// plus a polynomial MAC (GHASH) evaluated at H = AES(K, 0^128):
+//
+//  J0 = nonce || 0^31 || 1           (for the usual 96 bit nonce)
+//  C = CTR(K, inc32(J0), P)
+//  S = GHASH(H, A, C) = sum of b_i * H^(m - i + 1) over the blocks b_1..b_m of
+//                       A (zero padded) || C (zero padded) || len64(A) || len64(C)
+//  T = S + AES(K, J0)
+
+fn encrypt_block<C: BlockCipher>(cipher: &C, block: [u8; 16]) -> [u8; 16] {
+    let mut buf = block;
+    cipher.encrypt_block(&mut buf);
+    buf
+}
+
+/// the blocks GHASH runs over, in order: the aad, the ciphertext, then the length block
+pub fn ghash_blocks(aad: &[u8], ciphertext: &[u8]) -> Vec<Gf128> {
+    let mut blocks = vec![];
+    blocks.extend(aad.chunks(16).map(Gf128::from_block));
+    blocks.extend(ciphertext.chunks(16).map(Gf128::from_block));
+    let mut length_block = [0u8; 16];
+    length_block[..8].copy_from_slice(&((aad.len() as u64) * 8).to_be_bytes());
+    length_block[8..].copy_from_slice(&((ciphertext.len() as u64) * 8).to_be_bytes());
+    blocks.push(Gf128::from_block(&length_block));
+    blocks
+}
+
+pub fn ghash(h: Gf128, aad: &[u8], ciphertext: &[u8]) -> Gf128 {
+    ghash_blocks(aad, ciphertext)
+        .into_iter()
+        .fold(Gf128::zero(), |acc, block| (acc + block) * h)
+}
+
+pub fn authentication_key(key: Key) -> Result<Gf128> {
+    let cipher = Aes::new(key)?;
+    Ok(Gf128::from_block(&encrypt_block(&cipher, [0u8; 16])))
+}
+
+fn initial_counter_block(h: Gf128, nonce: &[u8]) -> [u8; 16] {
+    if nonce.len() == 12 {
+        let mut j0 = [0u8; 16];
+        j0[..12].copy_from_slice(nonce);
+        j0[15] = 1;
+        j0
+    } else {
+        // any other nonce length gets hashed down to a block
+        ghash(h, &[], nonce).to_block()
+    }
+}
+
+/// plain CTR with a 32 bit big endian counter, starting from inc32(J0)
+fn ctr<C: BlockCipher>(cipher: &C, j0: [u8; 16], input: &[u8]) -> Vec<u8> {
+    let counter = u32::from_be_bytes(j0[12..].try_into().unwrap()).wrapping_add(1);
+    let params = CtrParams::big_endian_32(&j0[..12], counter);
+    CtrStream::new(cipher, params).unwrap().process(input)
+}
+
+fn compute_tag<C: BlockCipher>(
+    cipher: &C,
+    h: Gf128,
+    j0: [u8; 16],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; 16] {
+    let s = ghash(h, aad, ciphertext);
+    let mask = Gf128::from_block(&encrypt_block(cipher, j0));
+    (s + mask).to_block()
+}
+
+/// returns (ciphertext, tag)
+pub fn encrypt(
+    plaintext: &[u8],
+    aad: &[u8],
+    key: Key,
+    nonce: &[u8],
+) -> Result<(Vec<u8>, [u8; 16])> {
+    let (ciphertext, tag) = encrypt_truncated(plaintext, aad, key, nonce, 16)?;
+    Ok((ciphertext, tag.try_into().unwrap()))
+}
+
+pub fn decrypt(
+    ciphertext: &[u8],
+    aad: &[u8],
+    tag: &[u8],
+    key: Key,
+    nonce: &[u8],
+) -> Result<Vec<u8>> {
+    decrypt_truncated(ciphertext, aad, tag, key, nonce, 16)
+}
+
+// SP 800-38D stops at 4 bytes. we go down to 1 on purpose, so the challenge 64 attack can be
+// tested on a tag it can forge in a fraction of a second
+fn check_tag_size(tag_size: usize) -> Result<()> {
+    if tag_size == 0 || tag_size > 16 {
+        return Err(anyhow!(
+            "GCM tag size must be 1 to 16 bytes, got {tag_size}"
+        ));
+    }
+    Ok(())
+}
+
+/// GCM with the tag cut down to its first tag_size bytes, anywhere from 1 to 16. SP 800-38D
+/// allows 4 and up, and warns loudly about the short ones -- see challenges 64 and 65 for why.
+pub fn encrypt_truncated(
+    plaintext: &[u8],
+    aad: &[u8],
+    key: Key,
+    nonce: &[u8],
+    tag_size: usize,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    if nonce.is_empty() {
+        return Err(anyhow!("GCM nonce must not be empty"));
+    }
+    check_tag_size(tag_size)?;
+    let cipher = Aes::new(key)?;
+    let h = Gf128::from_block(&encrypt_block(&cipher, [0u8; 16]));
+    let j0 = initial_counter_block(h, nonce);
+    let ciphertext = ctr(&cipher, j0, plaintext);
+    let tag = compute_tag(&cipher, h, j0, aad, &ciphertext);
+    Ok((ciphertext, tag[..tag_size].to_vec()))
+}
+
+/// the receiver decides the tag size, never the sender -- otherwise anyone could send a 1 byte tag
+pub fn decrypt_truncated(
+    ciphertext: &[u8],
+    aad: &[u8],
+    tag: &[u8],
+    key: Key,
+    nonce: &[u8],
+    tag_size: usize,
+) -> Result<Vec<u8>> {
+    if nonce.is_empty() {
+        return Err(anyhow!("GCM nonce must not be empty"));
+    }
+    check_tag_size(tag_size)?;
+    if tag.len() != tag_size {
+        return Err(anyhow!("Invalid tag"));
+    }
+    let cipher = Aes::new(key)?;
+    let h = Gf128::from_block(&encrypt_block(&cipher, [0u8; 16]));
+    let j0 = initial_counter_block(h, nonce);
+    let expected_tag = compute_tag(&cipher, h, j0, aad, ciphertext);
+    if expected_tag[..tag_size] != *tag {
+        return Err(anyhow!("Invalid tag"));
+    }
+    Ok(ctr(&cipher, j0, ciphertext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptopal_util::hex_to_bytes;
+
+    fn hex(s: &str) -> Vec<u8> {
+        hex_to_bytes(s.to_string()).unwrap()
+    }
+
+    // test cases 1 through 4, 6, 13 and 14 from the original GCM spec (McGrew & Viega)
+    #[test]
+    fn test_gcm_vectors() {
+        let cases = [
+            (
+                "00000000000000000000000000000000",
+                "000000000000000000000000",
+                "",
+                "",
+                "",
+                "58e2fccefa7e3061367f1d57a4e7455a",
+            ),
+            (
+                "00000000000000000000000000000000",
+                "000000000000000000000000",
+                "00000000000000000000000000000000",
+                "",
+                "0388dace60b6a392f328c2b971b2fe78",
+                "ab6e47d42cec13bdf53a67b21257bddf",
+            ),
+            (
+                "feffe9928665731c6d6a8f9467308308",
+                "cafebabefacedbaddecaf888",
+                "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+                "",
+                "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+                "4d5c2af327cd64a62cf35abd2ba6fab4",
+            ),
+            (
+                "feffe9928665731c6d6a8f9467308308",
+                "cafebabefacedbaddecaf888",
+                "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+                "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+                "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+                "5bc94fbc3221a5db94fae95ae7121a47",
+            ),
+            (
+                "feffe9928665731c6d6a8f9467308308",
+                "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+                "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+                "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+                "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca701e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+                "619cc5aefffe0bfa462af43c1699d050",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "000000000000000000000000",
+                "",
+                "",
+                "",
+                "530f8afbc74536b9a963b4f1c4cb738b",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "000000000000000000000000",
+                "00000000000000000000000000000000",
+                "",
+                "cea7403d4d606b6e074ec5d3baf39d18",
+                "d0d1c8a799996bf0265b98b5d48ab919",
+            ),
+        ];
+        for (key, nonce, plaintext, aad, ciphertext, tag) in cases {
+            let key = hex(key);
+            let (my_ciphertext, my_tag) =
+                encrypt(&hex(plaintext), &hex(aad), Key(&key), &hex(nonce)).unwrap();
+            assert_eq!(my_ciphertext, hex(ciphertext));
+            assert_eq!(my_tag.to_vec(), hex(tag));
+            let my_plaintext =
+                decrypt(&my_ciphertext, &hex(aad), &my_tag, Key(&key), &hex(nonce)).unwrap();
+            assert_eq!(my_plaintext, hex(plaintext));
+        }
+    }
+
+    #[test]
+    fn test_gcm_rejects_tampering() {
+        let key = [7u8; 16];
+        let nonce = [9u8; 12];
+        let (mut ciphertext, tag) =
+            encrypt(b"attack at dawn", b"header", Key(&key), &nonce).unwrap();
+        ciphertext[0] ^= 1;
+        assert!(decrypt(&ciphertext, b"header", &tag, Key(&key), &nonce).is_err());
+    }
+
+    #[test]
+    fn test_gcm_truncated_tags() {
+        let key = [7u8; 16];
+        let nonce = [9u8; 12];
+        let (_, full_tag) = encrypt(b"attack at dawn", b"", Key(&key), &nonce).unwrap();
+        let (ciphertext, tag) =
+            encrypt_truncated(b"attack at dawn", b"", Key(&key), &nonce, 4).unwrap();
+        assert_eq!(tag, full_tag[..4]);
+        assert!(decrypt_truncated(&ciphertext, b"", &tag, Key(&key), &nonce, 4).is_ok());
+        // a shorter tag than the receiver expects doesn't get a free pass
+        assert!(decrypt_truncated(&ciphertext, b"", &tag[..2], Key(&key), &nonce, 4).is_err());
+        // and nor does no tag at all, or one longer than a block
+        assert!(decrypt_truncated(&ciphertext, b"", &[], Key(&key), &nonce, 0).is_err());
+        let long_tag = [full_tag.as_slice(), &[0]].concat();
+        assert!(decrypt_truncated(&ciphertext, b"", &long_tag, Key(&key), &nonce, 17).is_err());
+        assert!(encrypt_truncated(b"attack at dawn", b"", Key(&key), &nonce, 0).is_err());
+        assert!(encrypt_truncated(b"attack at dawn", b"", Key(&key), &nonce, 17).is_err());
+    }
+}
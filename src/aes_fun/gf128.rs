@@ -0,0 +1,191 @@
+use crate::aes_fun::gf2_matrix::Gf2Matrix;
+use std::ops::{Add, Mul, Sub};
+
+// GF(2^128) as GCM sees it: the field is GF(2)[x] / (x^128 + x^7 + x^2 + x + 1), and a 16 byte
+// block maps onto a field element "backwards" -- the most significant bit of the first byte is the
+// coefficient of x^0, and the least significant bit of the last byte is the coefficient of x^127.
+//
+// We keep elements as a u128 loaded big endian from the block, so bit 127 of the u128 is x^0.
+
+// x^128 = x^7 + x^2 + x + 1, written in the same reflected bit order.
+const R: u128 = 0xe1 << 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Gf128(pub u128);
+
+impl Gf128 {
+    pub fn zero() -> Gf128 {
+        Gf128(0)
+    }
+
+    pub fn one() -> Gf128 {
+        Gf128(1 << 127)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// reads up to 16 bytes, zero padding on the right like GCM does with partial blocks
+    pub fn from_block(block: &[u8]) -> Gf128 {
+        assert!(block.len() <= 16);
+        let mut buf = [0u8; 16];
+        buf[..block.len()].copy_from_slice(block);
+        Gf128(u128::from_be_bytes(buf))
+    }
+
+    pub fn to_block(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn random() -> Gf128 {
+        Gf128(rand::random())
+    }
+
+    pub fn square(self) -> Gf128 {
+        self * self
+    }
+
+    pub fn pow(self, mut exponent: u128) -> Gf128 {
+        let mut result = Gf128::one();
+        let mut base = self;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base.square();
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// a^(2^128 - 2) = a^-1, because the multiplicative group has order 2^128 - 1
+    pub fn inverse(self) -> Option<Gf128> {
+        if self.is_zero() {
+            return None;
+        }
+        Some(self.pow(u128::MAX - 1))
+    }
+
+    /// squaring is the frobenius map, so the square root is squaring 127 more times
+    pub fn sqrt(self) -> Gf128 {
+        let mut result = self;
+        for _ in 0..127 {
+            result = result.square();
+        }
+        result
+    }
+
+    /// multiplying by x is just a shift (plus a reduction), no need for the full multiply
+    pub fn times_x(self) -> Gf128 {
+        if self.0 & 1 == 1 {
+            Gf128((self.0 >> 1) ^ R)
+        } else {
+            Gf128(self.0 >> 1)
+        }
+    }
+
+    /// x^i, for i in 0..128
+    pub fn monomial(i: usize) -> Gf128 {
+        Gf128(1 << (127 - i))
+    }
+
+    /// as a column vector over GF(2), entry i being the coefficient of x^i
+    pub fn to_bits(self) -> Vec<bool> {
+        (0..128).map(|i| (self.0 >> (127 - i)) & 1 == 1).collect()
+    }
+
+    pub fn from_bits(bits: &[bool]) -> Gf128 {
+        assert_eq!(bits.len(), 128);
+        Gf128(
+            bits.iter()
+                .enumerate()
+                .filter(|(_, &b)| b)
+                .fold(0, |acc, (i, _)| acc | 1 << (127 - i)),
+        )
+    }
+
+    /// the 128x128 matrix M_c with M_c * to_bits(a) = to_bits(c * a)
+    pub fn multiplication_matrix(self) -> Gf2Matrix {
+        let columns: Vec<Vec<bool>> = (0..128)
+            .map(|j| (self * Gf128::monomial(j)).to_bits())
+            .collect();
+        Gf2Matrix::from_columns(&columns, 128)
+    }
+
+    /// the 128x128 matrix M_s with M_s * to_bits(a) = to_bits(a^2). squaring is linear over GF(2)!
+    pub fn squaring_matrix() -> Gf2Matrix {
+        let columns: Vec<Vec<bool>> = (0..128)
+            .map(|j| Gf128::monomial(j).square().to_bits())
+            .collect();
+        Gf2Matrix::from_columns(&columns, 128)
+    }
+}
+
+impl Add for Gf128 {
+    type Output = Gf128;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Gf128) -> Gf128 {
+        Gf128(self.0 ^ rhs.0)
+    }
+}
+
+// characteristic 2, so subtraction is addition
+impl Sub for Gf128 {
+    type Output = Gf128;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Gf128) -> Gf128 {
+        Gf128(self.0 ^ rhs.0)
+    }
+}
+
+// Algorithm 1 from NIST SP 800-38D: shift-and-add, reducing by R whenever x^127 falls off the end.
+impl Mul for Gf128 {
+    type Output = Gf128;
+    fn mul(self, rhs: Gf128) -> Gf128 {
+        let mut z = 0u128;
+        let mut v = rhs.0;
+        for i in 0..128 {
+            if (self.0 >> (127 - i)) & 1 == 1 {
+                z ^= v;
+            }
+            if v & 1 == 1 {
+                v = (v >> 1) ^ R;
+            } else {
+                v >>= 1;
+            }
+        }
+        Gf128(z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Gf128;
+
+    #[test]
+    fn test_field_laws() {
+        for _ in 0..10 {
+            let a = Gf128::random();
+            let b = Gf128::random();
+            let c = Gf128::random();
+            assert_eq!(a * b, b * a);
+            assert_eq!(a * (b + c), a * b + a * c);
+            assert_eq!(a * Gf128::one(), a);
+            assert_eq!(a * a.inverse().unwrap(), Gf128::one());
+            assert_eq!(a.square().sqrt(), a);
+        }
+    }
+
+    #[test]
+    fn test_matrices() {
+        let a = Gf128::random();
+        let c = Gf128::random();
+        assert_eq!(Gf128::from_bits(&a.to_bits()), a);
+        assert_eq!(a.times_x(), a * Gf128::monomial(1));
+        let product = c.multiplication_matrix().mul_vector(&a.to_bits());
+        assert_eq!(Gf128::from_bits(&product), c * a);
+        let square = Gf128::squaring_matrix().mul_vector(&a.to_bits());
+        assert_eq!(Gf128::from_bits(&square), a.square());
+    }
+}
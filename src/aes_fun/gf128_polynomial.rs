@@ -0,0 +1,367 @@
+use crate::aes_fun::gf128::Gf128;
+
+// Polynomials over GF(2^128), plus enough factoring machinery to pull the roots out of one.
+//
+// The factoring pipeline is the textbook one for polynomials over a finite field GF(q):
+//  1. square-free factorization splits off repeated factors,
+//  2. distinct-degree factorization groups the irreducible factors by their degree,
+//  3. equal-degree factorization (Cantor-Zassenhaus) splits each group into its irreducibles.
+// Here q = 2^128, so the characteristic is 2 and a few of the steps look a little different.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    // lowest degree first, never any trailing zeros
+    coefficients: Vec<Gf128>,
+}
+
+impl Polynomial {
+    pub fn new(coefficients: Vec<Gf128>) -> Polynomial {
+        let mut p = Polynomial { coefficients };
+        p.normalize();
+        p
+    }
+
+    pub fn zero() -> Polynomial {
+        Polynomial::new(vec![])
+    }
+
+    pub fn one() -> Polynomial {
+        Polynomial::new(vec![Gf128::one()])
+    }
+
+    /// the polynomial "x"
+    pub fn x() -> Polynomial {
+        Polynomial::new(vec![Gf128::zero(), Gf128::one()])
+    }
+
+    pub fn random(degree_below: usize) -> Polynomial {
+        Polynomial::new((0..degree_below).map(|_| Gf128::random()).collect())
+    }
+
+    fn normalize(&mut self) {
+        while let Some(c) = self.coefficients.last() {
+            if c.is_zero() {
+                self.coefficients.pop();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn coefficients(&self) -> &[Gf128] {
+        &self.coefficients
+    }
+
+    /// None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.coefficients == [Gf128::one()]
+    }
+
+    pub fn leading_coefficient(&self) -> Gf128 {
+        self.coefficients.last().copied().unwrap_or_default()
+    }
+
+    pub fn evaluate(&self, x: Gf128) -> Gf128 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Gf128::zero(), |acc, &c| acc * x + c)
+    }
+
+    pub fn add(&self, other: &Polynomial) -> Polynomial {
+        let len = self.coefficients.len().max(other.coefficients.len());
+        let mut coefficients = vec![Gf128::zero(); len];
+        for (i, c) in self.coefficients.iter().enumerate() {
+            coefficients[i] = coefficients[i] + *c;
+        }
+        for (i, c) in other.coefficients.iter().enumerate() {
+            coefficients[i] = coefficients[i] + *c;
+        }
+        Polynomial::new(coefficients)
+    }
+
+    pub fn scale(&self, by: Gf128) -> Polynomial {
+        Polynomial::new(self.coefficients.iter().map(|&c| c * by).collect())
+    }
+
+    pub fn mul(&self, other: &Polynomial) -> Polynomial {
+        if self.is_zero() || other.is_zero() {
+            return Polynomial::zero();
+        }
+        let mut coefficients =
+            vec![Gf128::zero(); self.coefficients.len() + other.coefficients.len() - 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                coefficients[i + j] = coefficients[i + j] + *a * *b;
+            }
+        }
+        Polynomial::new(coefficients)
+    }
+
+    /// returns (quotient, remainder). panics on division by zero, like integer division does.
+    pub fn div_rem(&self, divisor: &Polynomial) -> (Polynomial, Polynomial) {
+        let divisor_degree = divisor.degree().expect("polynomial division by zero");
+        let lead_inverse = divisor.leading_coefficient().inverse().unwrap();
+        let mut remainder = self.coefficients.clone();
+        if remainder.len() <= divisor_degree {
+            return (Polynomial::zero(), self.clone());
+        }
+        let mut quotient = vec![Gf128::zero(); remainder.len() - divisor_degree];
+        for i in (0..quotient.len()).rev() {
+            let factor = remainder[i + divisor_degree] * lead_inverse;
+            quotient[i] = factor;
+            for (j, d) in divisor.coefficients.iter().enumerate() {
+                remainder[i + j] = remainder[i + j] - factor * *d;
+            }
+        }
+        (Polynomial::new(quotient), Polynomial::new(remainder))
+    }
+
+    pub fn rem(&self, modulus: &Polynomial) -> Polynomial {
+        self.div_rem(modulus).1
+    }
+
+    pub fn monic(&self) -> Polynomial {
+        match self.leading_coefficient().inverse() {
+            Some(inverse) => self.scale(inverse),
+            None => Polynomial::zero(),
+        }
+    }
+
+    /// monic gcd
+    pub fn gcd(&self, other: &Polynomial) -> Polynomial {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let r = a.rem(&b);
+            a = b;
+            b = r;
+        }
+        a.monic()
+    }
+
+    /// formal derivative. in characteristic 2 the even-degree terms all vanish.
+    pub fn derivative(&self) -> Polynomial {
+        Polynomial::new(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &c)| if i % 2 == 1 { c } else { Gf128::zero() })
+                .collect(),
+        )
+    }
+
+    /// only meaningful when the derivative is zero, i.e. every term has even degree.
+    /// (sum a_i x^2i)^(1/2) = sum sqrt(a_i) x^i, since squaring is linear in characteristic 2.
+    pub fn sqrt(&self) -> Polynomial {
+        Polynomial::new(
+            self.coefficients
+                .iter()
+                .step_by(2)
+                .map(|c| c.sqrt())
+                .collect(),
+        )
+    }
+
+    pub fn mul_mod(&self, other: &Polynomial, modulus: &Polynomial) -> Polynomial {
+        self.mul(other).rem(modulus)
+    }
+
+    pub fn pow(&self, mut exponent: usize) -> Polynomial {
+        let mut result = Polynomial::one();
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// self^(2^128) mod modulus, the frobenius map for q = 2^128
+    fn frobenius_mod(&self, modulus: &Polynomial) -> Polynomial {
+        let mut result = self.clone();
+        for _ in 0..128 {
+            result = result.mul_mod(&result, modulus);
+        }
+        result
+    }
+}
+
+/// returns (factor, multiplicity) pairs whose factors are square-free and pairwise coprime
+pub fn square_free_factorization(f: &Polynomial) -> Vec<(Polynomial, usize)> {
+    let mut factors = vec![];
+    if f.degree().unwrap_or(0) == 0 {
+        return factors;
+    }
+    let f = f.monic();
+    let derivative = f.derivative();
+    if derivative.is_zero() {
+        // f is a perfect square
+        for (factor, multiplicity) in square_free_factorization(&f.sqrt()) {
+            factors.push((factor, multiplicity * 2));
+        }
+        return factors;
+    }
+
+    let mut c = f.gcd(&derivative);
+    let mut w = f.div_rem(&c).0;
+    let mut i = 1;
+    while !w.is_one() {
+        let y = w.gcd(&c);
+        let factor = w.div_rem(&y).0;
+        if !factor.is_one() {
+            factors.push((factor, i));
+        }
+        w = y;
+        c = c.div_rem(&w).0;
+        i += 1;
+    }
+    // whatever is left in c is a square whose factors all had multiplicity divisible by 2
+    if !c.is_one() {
+        for (factor, multiplicity) in square_free_factorization(&c.sqrt()) {
+            factors.push((factor, multiplicity * 2));
+        }
+    }
+    factors
+}
+
+/// f must be square-free. returns (product of all irreducible factors of degree d, d) pairs
+pub fn distinct_degree_factorization(f: &Polynomial) -> Vec<(Polynomial, usize)> {
+    let mut factors = vec![];
+    let mut f_star = f.monic();
+    let mut d = 1;
+    // x^(q^d) mod f_star
+    let mut x_to_the_q_to_the_d = Polynomial::x();
+    while f_star.degree().unwrap_or(0) >= 2 * d {
+        x_to_the_q_to_the_d = x_to_the_q_to_the_d.frobenius_mod(&f_star);
+        // every irreducible of degree dividing d divides x^(q^d) - x
+        let g = f_star.gcd(&x_to_the_q_to_the_d.add(&Polynomial::x()));
+        if !g.is_one() {
+            f_star = f_star.div_rem(&g).0;
+            x_to_the_q_to_the_d = x_to_the_q_to_the_d.rem(&f_star);
+            factors.push((g, d));
+        }
+        d += 1;
+    }
+    if let Some(degree) = f_star.degree() {
+        if degree > 0 {
+            factors.push((f_star, degree));
+        }
+    }
+    factors
+}
+
+/// f must be a product of distinct irreducibles of degree d. returns those irreducibles (monic).
+pub fn equal_degree_factorization(f: &Polynomial, d: usize) -> Vec<Polynomial> {
+    let f = f.monic();
+    let n = f.degree().unwrap_or(0);
+    if n == 0 {
+        return vec![];
+    }
+    let number_of_factors = n / d;
+    let mut factors = vec![f.clone()];
+    while factors.len() < number_of_factors {
+        // in characteristic 2 the usual h^((q^d - 1) / 2) trick doesn't work. instead use the
+        // trace down to GF(2): it maps each of the GF(q^d) components of h to 0 or 1, so gcd'ing
+        // with it splits f about half the time.
+        let h = Polynomial::random(n);
+        let mut trace = h.clone();
+        let mut term = h;
+        for _ in 1..128 * d {
+            term = term.mul_mod(&term, &f);
+            trace = trace.add(&term);
+        }
+        let mut next_factors = vec![];
+        for u in factors {
+            if u.degree() == Some(d) {
+                next_factors.push(u);
+                continue;
+            }
+            let g = u.gcd(&trace);
+            if g.is_one() || g == u {
+                next_factors.push(u);
+            } else {
+                next_factors.push(u.div_rem(&g).0);
+                next_factors.push(g);
+            }
+        }
+        factors = next_factors;
+    }
+    factors
+}
+
+/// every distinct root of f in GF(2^128)
+pub fn roots(f: &Polynomial) -> Vec<Gf128> {
+    let mut roots = vec![];
+    for (square_free, _) in square_free_factorization(f) {
+        for (product, d) in distinct_degree_factorization(&square_free) {
+            if d != 1 {
+                continue;
+            }
+            // each linear factor is monic, x + a, so its root is a (since -a = a)
+            for linear in equal_degree_factorization(&product, 1) {
+                roots.push(linear.coefficients()[0]);
+            }
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear(root: Gf128) -> Polynomial {
+        Polynomial::new(vec![root, Gf128::one()])
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let a = Polynomial::random(7);
+        let b = Polynomial::random(4);
+        let (q, r) = a.div_rem(&b);
+        assert!(r.degree() < b.degree());
+        assert_eq!(q.mul(&b).add(&r), a);
+    }
+
+    #[test]
+    fn test_square_free_factorization() {
+        let a = linear(Gf128::random());
+        let b = linear(Gf128::random());
+        let c = Polynomial::random(3).monic();
+        let f = a.mul(&b.pow(2)).mul(&c.pow(3));
+        let factors = square_free_factorization(&f);
+        let product = factors
+            .iter()
+            .fold(Polynomial::one(), |acc, (g, m)| acc.mul(&g.pow(*m)));
+        assert_eq!(product, f.monic());
+        assert!(factors.contains(&(b, 2)));
+    }
+
+    #[test]
+    fn test_roots() {
+        let expected: Vec<Gf128> = (0..4).map(|_| Gf128::random()).collect();
+        // multiply in an irreducible-ish quadratic and a repeated root to give the factoring some work
+        let f = expected
+            .iter()
+            .fold(Polynomial::random(3), |acc, &r| acc.mul(&linear(r)))
+            .mul(&linear(expected[0]));
+        let found = roots(&f);
+        for r in expected.iter() {
+            assert!(found.contains(r));
+            assert!(f.evaluate(*r).is_zero());
+        }
+    }
+}
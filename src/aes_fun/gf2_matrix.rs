@@ -0,0 +1,319 @@
+// Dense matrices over GF(2), bit packed, with just enough linear algebra for the truncated-MAC
+// GCM attacks: multiplication, transposes, kernels and solving Ax = b.
+//
+// Vectors are plain Vec<bool>s at the edges; internally each row is a run of u64 words.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gf2Matrix {
+    rows: usize,
+    cols: usize,
+    // words per row
+    stride: usize,
+    data: Vec<u64>,
+}
+
+impl Gf2Matrix {
+    pub fn zero(rows: usize, cols: usize) -> Gf2Matrix {
+        let stride = cols.div_ceil(64);
+        Gf2Matrix {
+            rows,
+            cols,
+            stride,
+            data: vec![0; rows * stride],
+        }
+    }
+
+    pub fn identity(n: usize) -> Gf2Matrix {
+        let mut m = Gf2Matrix::zero(n, n);
+        for i in 0..n {
+            m.set(i, i, true);
+        }
+        m
+    }
+
+    pub fn from_rows(rows: &[Vec<bool>], cols: usize) -> Gf2Matrix {
+        let mut m = Gf2Matrix::zero(rows.len(), cols);
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), cols);
+            for (j, &bit) in row.iter().enumerate() {
+                m.set(i, j, bit);
+            }
+        }
+        m
+    }
+
+    pub fn from_columns(columns: &[Vec<bool>], rows: usize) -> Gf2Matrix {
+        Gf2Matrix::from_rows(columns, rows).transpose()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        (self.data[row * self.stride + col / 64] >> (col % 64)) & 1 == 1
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, bit: bool) {
+        let word = &mut self.data[row * self.stride + col / 64];
+        if bit {
+            *word |= 1 << (col % 64);
+        } else {
+            *word &= !(1 << (col % 64));
+        }
+    }
+
+    pub fn row(&self, row: usize) -> Vec<bool> {
+        (0..self.cols).map(|j| self.get(row, j)).collect()
+    }
+
+    pub fn column(&self, col: usize) -> Vec<bool> {
+        (0..self.rows).map(|i| self.get(i, col)).collect()
+    }
+
+    fn row_words(&self, row: usize) -> &[u64] {
+        &self.data[row * self.stride..(row + 1) * self.stride]
+    }
+
+    fn row_is_zero(&self, row: usize) -> bool {
+        self.row_words(row).iter().all(|&w| w == 0)
+    }
+
+    /// row dst ^= row src
+    fn xor_row(&mut self, dst: usize, src: usize) {
+        let stride = self.stride;
+        let (dst_words, src_words) = if dst < src {
+            let (head, tail) = self.data.split_at_mut(src * stride);
+            (&mut head[dst * stride..(dst + 1) * stride], &tail[..stride])
+        } else {
+            let (head, tail) = self.data.split_at_mut(dst * stride);
+            (&mut tail[..stride], &head[src * stride..(src + 1) * stride])
+        };
+        for (d, s) in dst_words.iter_mut().zip(src_words.iter()) {
+            *d ^= s;
+        }
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for w in 0..self.stride {
+            self.data.swap(a * self.stride + w, b * self.stride + w);
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.data.iter().all(|&w| w == 0)
+    }
+
+    pub fn transpose(&self) -> Gf2Matrix {
+        let mut t = Gf2Matrix::zero(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                if self.get(i, j) {
+                    t.set(j, i, true);
+                }
+            }
+        }
+        t
+    }
+
+    pub fn add(&self, other: &Gf2Matrix) -> Gf2Matrix {
+        assert_eq!((self.rows, self.cols), (other.rows, other.cols));
+        let mut sum = self.clone();
+        for (a, b) in sum.data.iter_mut().zip(other.data.iter()) {
+            *a ^= b;
+        }
+        sum
+    }
+
+    pub fn mul(&self, other: &Gf2Matrix) -> Gf2Matrix {
+        assert_eq!(self.cols, other.rows);
+        let mut product = Gf2Matrix::zero(self.rows, other.cols);
+        // row i of the product is the sum of the rows of other picked out by row i of self
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                if self.get(i, j) {
+                    for w in 0..product.stride {
+                        product.data[i * product.stride + w] ^= other.data[j * other.stride + w];
+                    }
+                }
+            }
+        }
+        product
+    }
+
+    pub fn mul_vector(&self, v: &[bool]) -> Vec<bool> {
+        assert_eq!(self.cols, v.len());
+        (0..self.rows)
+            .map(|i| (0..self.cols).filter(|&j| v[j] && self.get(i, j)).count() % 2 == 1)
+            .collect()
+    }
+
+    /// the first n rows
+    pub fn take_rows(&self, n: usize) -> Gf2Matrix {
+        assert!(n <= self.rows);
+        Gf2Matrix {
+            rows: n,
+            cols: self.cols,
+            stride: self.stride,
+            data: self.data[..n * self.stride].to_vec(),
+        }
+    }
+
+    /// stack other underneath self
+    pub fn append_rows(&mut self, other: &Gf2Matrix) {
+        assert_eq!(self.cols, other.cols);
+        self.rows += other.rows;
+        self.data.extend_from_slice(&other.data);
+    }
+
+    /// reduced row echelon form in place, returning the pivot column of each nonzero row.
+    /// only the first `cols` columns are eligible as pivots, so an augmented column can ride along.
+    fn reduce(&mut self, cols: usize) -> Vec<usize> {
+        let mut pivots = vec![];
+        let mut next_row = 0;
+        for col in 0..cols {
+            if next_row == self.rows {
+                break;
+            }
+            let Some(pivot_row) = (next_row..self.rows).find(|&r| self.get(r, col)) else {
+                continue;
+            };
+            self.swap_rows(next_row, pivot_row);
+            for r in 0..self.rows {
+                if r != next_row && self.get(r, col) {
+                    self.xor_row(r, next_row);
+                }
+            }
+            pivots.push(col);
+            next_row += 1;
+        }
+        pivots
+    }
+
+    pub fn rank(&self) -> usize {
+        self.clone().reduce(self.cols).len()
+    }
+
+    /// given a reduced matrix and its pivots, a basis for its kernel, one basis vector per row
+    fn kernel_of_reduced(reduced: &Gf2Matrix, pivots: &[usize], cols: usize) -> Gf2Matrix {
+        let mut is_pivot = vec![false; cols];
+        for &p in pivots.iter() {
+            is_pivot[p] = true;
+        }
+        let free_columns: Vec<usize> = (0..cols).filter(|&c| !is_pivot[c]).collect();
+        let mut basis = Gf2Matrix::zero(free_columns.len(), cols);
+        for (i, &free) in free_columns.iter().enumerate() {
+            basis.set(i, free, true);
+            for (row, &p) in pivots.iter().enumerate() {
+                basis.set(i, p, reduced.get(row, free));
+            }
+        }
+        basis
+    }
+
+    /// a basis for { x : self * x = 0 }, one basis vector per row
+    pub fn kernel(&self) -> Gf2Matrix {
+        let mut reduced = self.clone();
+        let pivots = reduced.reduce(self.cols);
+        Gf2Matrix::kernel_of_reduced(&reduced, &pivots, self.cols)
+    }
+
+    /// the sum of the rows picked out by select
+    pub fn sum_rows(&self, select: &[bool]) -> Vec<bool> {
+        assert_eq!(select.len(), self.rows);
+        let mut sum = vec![0u64; self.stride];
+        for (r, _) in select.iter().enumerate().filter(|(_, &s)| s) {
+            for (acc, w) in sum.iter_mut().zip(self.row_words(r)) {
+                *acc ^= w;
+            }
+        }
+        (0..self.cols)
+            .map(|j| (sum[j / 64] >> (j % 64)) & 1 == 1)
+            .collect()
+    }
+
+    /// some x with self * x = rhs, if there is one
+    pub fn solve(&self, rhs: &[bool]) -> Option<Vec<bool>> {
+        self.solve_all(rhs).map(|(x, _)| x)
+    }
+
+    /// every x with self * x = rhs, as one solution plus a basis for the kernel
+    pub fn solve_all(&self, rhs: &[bool]) -> Option<(Vec<bool>, Gf2Matrix)> {
+        assert_eq!(rhs.len(), self.rows);
+        let mut augmented = Gf2Matrix::zero(self.rows, self.cols + 1);
+        for (i, &b) in rhs.iter().enumerate() {
+            for j in 0..self.cols {
+                augmented.set(i, j, self.get(i, j));
+            }
+            augmented.set(i, self.cols, b);
+        }
+        let pivots = augmented.reduce(self.cols);
+        // a leftover row of 0 = 1 means the system is inconsistent
+        for row in pivots.len()..self.rows {
+            if augmented.get(row, self.cols) {
+                return None;
+            }
+        }
+        let mut x = vec![false; self.cols];
+        for (row, &p) in pivots.iter().enumerate() {
+            x[p] = augmented.get(row, self.cols);
+        }
+        let kernel = Gf2Matrix::kernel_of_reduced(&augmented, &pivots, self.cols);
+        Some((x, kernel))
+    }
+
+    /// drops all-zero rows
+    pub fn without_zero_rows(&self) -> Gf2Matrix {
+        let mut m = Gf2Matrix::zero(0, self.cols);
+        for r in 0..self.rows {
+            if !self.row_is_zero(r) {
+                m.rows += 1;
+                m.data.extend_from_slice(self.row_words(r));
+            }
+        }
+        m
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Gf2Matrix;
+    use rand::Rng;
+
+    fn random_matrix(rows: usize, cols: usize) -> Gf2Matrix {
+        let mut rng = rand::thread_rng();
+        let mut m = Gf2Matrix::zero(rows, cols);
+        for i in 0..rows {
+            for j in 0..cols {
+                m.set(i, j, rng.gen());
+            }
+        }
+        m
+    }
+
+    #[test]
+    fn test_kernel_and_solve() {
+        let a = random_matrix(70, 100);
+        let kernel = a.kernel();
+        assert_eq!(kernel.rows(), 100 - a.rank());
+        assert!(a.mul(&kernel.transpose()).is_zero());
+        let select: Vec<bool> = (0..kernel.rows()).map(|_| rand::random()).collect();
+        assert!(a.mul_vector(&kernel.sum_rows(&select)).iter().all(|&b| !b));
+        let x: Vec<bool> = (0..100).map(|_| rand::random()).collect();
+        let b = a.mul_vector(&x);
+        let solution = a.solve(&b).unwrap();
+        assert_eq!(a.mul_vector(&solution), b);
+    }
+
+    #[test]
+    fn test_mul_transpose() {
+        let a = random_matrix(5, 70);
+        let b = random_matrix(70, 9);
+        assert_eq!(a.mul(&b).transpose(), b.transpose().mul(&a.transpose()));
+        assert_eq!(a.mul(&Gf2Matrix::identity(70)), a);
+    }
+}
@@ -1,6 +1,19 @@
+pub mod bitflipping;
+pub mod block_cipher;
 pub mod cbc;
+pub mod cfb;
 pub mod ctr;
 pub mod ecb;
+pub mod ecb_cut_and_paste;
+pub mod ecb_oracle;
+pub mod feedback_mode_attacks;
+pub mod fixed_nonce_ctr;
+pub mod gcm;
+pub mod gf128;
+pub mod gf128_polynomial;
+pub mod gf2_matrix;
+pub mod ofb;
+pub mod padding_oracle;
 
 pub mod challenge_11;
 pub mod challenge_12;
@@ -12,6 +25,9 @@ pub mod challenge_19_and_20;
 pub mod challenge_25;
 pub mod challenge_26;
 pub mod challenge_27;
+pub mod challenge_63;
+pub mod challenge_64;
+pub mod challenge_65;
 
 pub struct Key<'a>(pub &'a [u8]);
 pub struct Iv<'a>(pub &'a [u8]);
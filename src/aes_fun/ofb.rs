@@ -0,0 +1,114 @@
+use super::{Iv, Key};
+use crate::aes_fun::block_cipher::{Aes, BlockCipher};
+use anyhow::{anyhow, Result};
+
+// OFB: encrypt the IV over and over, and use the blocks as a keystream.
+//
+//  O_1 = E(IV), O_i = E(O_(i-1))
+//  C_i = P_i xor O_i
+//
+// Like CTR, the keystream doesn't depend on the plaintext at all, so there's no padding, flipping a
+// ciphertext bit flips the same plaintext bit, and reusing an IV reuses the whole keystream.
+
+/// OFB as a stream: feed it data in pieces of any size, encryption and decryption are the same
+pub struct OfbStream<C: BlockCipher> {
+    cipher: C,
+    keystream_block: Vec<u8>,
+    // how much of keystream_block is used up
+    position: usize,
+}
+
+impl<C: BlockCipher> OfbStream<C> {
+    pub fn new(cipher: C, iv: Iv) -> Result<OfbStream<C>> {
+        if iv.0.len() != cipher.block_size() {
+            return Err(anyhow!(
+                "OFB IV must be {} bytes, got {}",
+                cipher.block_size(),
+                iv.0.len()
+            ));
+        }
+        let position = cipher.block_size();
+        Ok(OfbStream {
+            cipher,
+            keystream_block: iv.0.to_vec(),
+            position,
+        })
+    }
+
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.position == self.keystream_block.len() {
+                self.cipher.encrypt_block(&mut self.keystream_block);
+                self.position = 0;
+            }
+            *byte ^= self.keystream_block[self.position];
+            self.position += 1;
+        }
+    }
+
+    pub fn process(&mut self, input: &[u8]) -> Vec<u8> {
+        let mut output = input.to_vec();
+        self.apply_keystream(&mut output);
+        output
+    }
+}
+
+pub fn encrypt(plaintext: &[u8], key: Key, iv: Iv) -> Result<Vec<u8>> {
+    Ok(OfbStream::new(Aes::new(key)?, iv)?.process(plaintext))
+}
+
+pub fn decrypt(ciphertext: &[u8], key: Key, iv: Iv) -> Result<Vec<u8>> {
+    encrypt(ciphertext, key, iv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::block_cipher::sp800_38a::*;
+
+    #[test]
+    fn test_sp800_38a_vectors() {
+        let cases = [
+            (
+                KEY_128,
+                "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+                 9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e",
+            ),
+            (
+                KEY_192,
+                "cdc80d6fddf18cab34c25909c99a4174fcc28b8d4c63837c09e81700c1100401\
+                 8d9a9aeac0f6596f559c6d4daf59a5f26d9f200857ca6c3e9cac524bd9acc92a",
+            ),
+            (
+                KEY_256,
+                "dc7e84bfda79164b7ecd8486985d38604febdc6740d20b3ac88f6ad82a4fb08d\
+                 71ab47a086e86eedf39d1c5bba97c4080126141d67f37be8538f5a8be740e484",
+            ),
+        ];
+        for (key, ciphertext) in cases {
+            let key = hex(key);
+            let iv = hex(IV);
+            assert_eq!(
+                encrypt(&hex(PLAINTEXT), Key(&key), Iv(&iv)).unwrap(),
+                hex(ciphertext)
+            );
+            assert_eq!(
+                decrypt(&hex(ciphertext), Key(&key), Iv(&iv)).unwrap(),
+                hex(PLAINTEXT)
+            );
+            // any way of chopping up the input gives the same stream
+            let mut stream = OfbStream::new(Aes::new(Key(&key)).unwrap(), Iv(&iv)).unwrap();
+            let plaintext = hex(PLAINTEXT);
+            let mut pieces = stream.process(&plaintext[..5]);
+            pieces.extend(stream.process(&plaintext[5..37]));
+            pieces.extend(stream.process(&plaintext[37..]));
+            assert_eq!(pieces, hex(ciphertext));
+        }
+    }
+
+    #[test]
+    fn test_bad_sizes() {
+        assert!(encrypt(b"hello", Key(b"short key"), Iv(&[0; 16])).is_err());
+        assert!(encrypt(b"hello", Key(b"YELLOW SUBMARINE"), Iv(&[0; 12])).is_err());
+    }
+}
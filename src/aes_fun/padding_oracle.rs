@@ -0,0 +1,178 @@
+// The CBC padding oracle attack from challenge 17, pulled out so it works against anything that
+// will tell us whether a ciphertext's padding is good.
+//
+// CBC decrypts a block as P_i = D(C_i) xor C_(i-1), and we get to choose C_(i-1). Put a block of
+// our own in front of C_i, and walk its last byte through all 256 values: exactly one of them
+// (barring accidents, see below) makes the last byte of the "plaintext" 0x01, which is valid
+// padding. That gives us the last byte of D(C_i). Then fix our last byte so it decrypts to 0x02
+// and walk the byte before it, and so on until we have all of D(C_i). xor with the real C_(i-1)
+// and that's the plaintext.
+//
+// The accident: when we're on the last byte, the byte before it might happen to decrypt to 0x02,
+// and then 0x02 0x02 is valid padding too. Flip the byte before and ask again -- 0x01 doesn't care
+// what comes before it, 0x02 0x02 does.
+//
+// And since D(C_i) is all we ever learn, we can run it backwards too (CBC-R): pick any last
+// ciphertext block, learn D of it, choose the block before it so that the xor is whatever
+// plaintext we want, and repeat back to the IV. Encryption without the key.
+
+use crate::pkcs7::{pkcs7_pad, pkcs7_unpad};
+use anyhow::{anyhow, Result};
+use rand::Rng;
+
+/// anything that decrypts CBC and lets slip whether the padding was valid
+pub trait PaddingOracle {
+    fn is_valid(&self, iv: &[u8], ct: &[u8]) -> bool;
+}
+
+pub struct PaddingOracleAttacker<'a, O: PaddingOracle + ?Sized> {
+    oracle: &'a O,
+    block_size: usize,
+    queries: usize,
+}
+
+impl<'a, O: PaddingOracle + ?Sized> PaddingOracleAttacker<'a, O> {
+    pub fn new(oracle: &'a O, block_size: usize) -> PaddingOracleAttacker<'a, O> {
+        PaddingOracleAttacker {
+            oracle,
+            block_size,
+            queries: 0,
+        }
+    }
+
+    /// how many times we've asked the oracle so far
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    fn ask(&mut self, iv: &[u8], ct: &[u8]) -> bool {
+        self.queries += 1;
+        self.oracle.is_valid(iv, ct)
+    }
+
+    /// D(block), the raw block cipher decryption, one byte at a time from the end
+    pub fn decrypt_block(&mut self, block: &[u8]) -> Result<Vec<u8>> {
+        let block_size = self.block_size;
+        if block.len() != block_size || block_size == 0 || block_size > 255 {
+            return Err(anyhow!(
+                "can't attack a {} byte block with a {block_size} byte block size",
+                block.len()
+            ));
+        }
+        let mut intermediate = vec![0u8; block_size];
+        for byte in (0..block_size).rev() {
+            let padding_val = (block_size - byte) as u8;
+            let mut forged_iv = vec![0u8; block_size];
+            // everything after this byte decrypts to the padding value
+            for i in byte + 1..block_size {
+                forged_iv[i] = intermediate[i] ^ padding_val;
+            }
+            let mut found = None;
+            for guess in 0..=255u8 {
+                forged_iv[byte] = guess;
+                if !self.ask(&forged_iv, block) {
+                    continue;
+                }
+                if byte == block_size - 1 && byte > 0 {
+                    // make sure that was 0x01 and not 0x02 0x02 (or 0x03 0x03 0x03...)
+                    forged_iv[byte - 1] ^= 1;
+                    let still_valid = self.ask(&forged_iv, block);
+                    forged_iv[byte - 1] ^= 1;
+                    if !still_valid {
+                        continue;
+                    }
+                }
+                found = Some(guess);
+                break;
+            }
+            let guess = found.ok_or_else(|| anyhow!("the oracle never accepted byte {byte}"))?;
+            intermediate[byte] = guess ^ padding_val;
+        }
+        Ok(intermediate)
+    }
+
+    /// the padded plaintext of a whole CBC ciphertext
+    pub fn decrypt_no_unpad(&mut self, iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        if iv.len() != self.block_size || !ciphertext.len().is_multiple_of(self.block_size) {
+            return Err(anyhow!(
+                "the IV and ciphertext must be made of {} byte blocks",
+                self.block_size
+            ));
+        }
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        let mut previous_block = iv;
+        for block in ciphertext.chunks(self.block_size) {
+            let intermediate = self.decrypt_block(block)?;
+            plaintext.extend(intermediate.iter().zip(previous_block).map(|(a, b)| a ^ b));
+            previous_block = block;
+        }
+        Ok(plaintext)
+    }
+
+    pub fn decrypt(&mut self, iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        pkcs7_unpad(&self.decrypt_no_unpad(iv, ciphertext)?)
+    }
+
+    /// CBC-R: returns (iv, ciphertext) that the oracle's owner will decrypt to plaintext
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let padded = pkcs7_pad(plaintext, self.block_size);
+        // any last block will do
+        let mut last_block = vec![0u8; self.block_size];
+        rand::thread_rng().fill(last_block.as_mut_slice());
+        let mut blocks = vec![last_block];
+        for plaintext_block in padded.chunks(self.block_size).rev() {
+            let intermediate = self.decrypt_block(blocks.last().unwrap())?;
+            blocks.push(
+                intermediate
+                    .iter()
+                    .zip(plaintext_block)
+                    .map(|(a, b)| a ^ b)
+                    .collect(),
+            );
+        }
+        // the block we made last goes first: it's the IV
+        blocks.reverse();
+        let iv = blocks.remove(0);
+        Ok((iv, blocks.concat()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_fun::block_cipher::{Aes, BlockCipher, ToyCipher};
+    use crate::aes_fun::{cbc, Iv, Key};
+
+    struct Server<C: BlockCipher>(C);
+
+    impl<C: BlockCipher> PaddingOracle for Server<C> {
+        fn is_valid(&self, iv: &[u8], ct: &[u8]) -> bool {
+            cbc::decrypt_with(&self.0, ct, Iv(iv)).is_ok()
+        }
+    }
+
+    #[test]
+    fn test_decrypt_and_encrypt() {
+        let message = b"we all live in a yellow submarine";
+        let aes_server = Server(Aes::new(Key(b"YELLOW SUBMARINE")).unwrap());
+        let toy_server = Server(ToyCipher(0x5a));
+        let servers: [(&dyn PaddingOracle, &dyn BlockCipher); 2] =
+            [(&aes_server, &aes_server.0), (&toy_server, &toy_server.0)];
+        for (oracle, cipher) in servers {
+            let block_size = cipher.block_size();
+            let iv = vec![7u8; block_size];
+            let ciphertext = cbc::encrypt_with(&cipher, message, Iv(&iv)).unwrap();
+            let mut attacker = PaddingOracleAttacker::new(oracle, block_size);
+            assert_eq!(attacker.decrypt(&iv, &ciphertext).unwrap(), message);
+            // about 128 queries a byte
+            let blocks = ciphertext.len() / block_size;
+            assert!(attacker.queries() < blocks * block_size * 256);
+
+            let (forged_iv, forged) = attacker.encrypt(b"attack at dawn, bring snacks").unwrap();
+            assert_eq!(
+                cbc::decrypt_with(&cipher, &forged, Iv(&forged_iv)).unwrap(),
+                b"attack at dawn, bring snacks"
+            );
+        }
+    }
+}
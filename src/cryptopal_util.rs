@@ -140,15 +140,17 @@ pub fn bytes_to_biguint(bytes: &[u8]) -> BigUint {
 }
 
 pub fn biguint_to_bytes(biguint: &BigUint) -> Vec<u8> {
-    // Convert the biguint to a hex string:
-    let hex_string = format!("{biguint:x}");
-    // Convert the hex string to bytes:
-    hex_to_bytes(hex_string).unwrap()
+    // Big endian, no leading zeros, and nothing at all for zero:
+    if biguint.is_zero() {
+        return vec![];
+    }
+    biguint.to_bytes_be()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cryptopal_util::{ascii_to_bytes, hamming_distance};
+    use crate::cryptopal_util::{ascii_to_bytes, biguint_to_bytes, hamming_distance};
+    use num::BigUint;
 
     #[test]
     fn check_hamming_distance() {
@@ -159,4 +161,12 @@ mod tests {
         let my_output = hamming_distance(&bytes1, &bytes2);
         assert_eq!(my_output, 37);
     }
+
+    #[test]
+    fn check_biguint_to_bytes() {
+        // 0x1ff has an odd number of hex digits
+        assert_eq!(biguint_to_bytes(&BigUint::from(0x1ffu32)), [0x01, 0xff]);
+        assert_eq!(biguint_to_bytes(&BigUint::from(0xabcdu32)), [0xab, 0xcd]);
+        assert!(biguint_to_bytes(&BigUint::from(0u32)).is_empty());
+    }
 }
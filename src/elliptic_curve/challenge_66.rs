@@ -0,0 +1,171 @@
+// Exploiting Implementation Errors in Diffie-Hellman
+// Bob's bignum library has a carry bug. Once in a blue moon, multiplying two numbers goes wrong,
+// and whatever Bob was computing at the time blows up. Bob's ECDH scalar multiplication is the
+// usual double-and-add:
+//
+//  R = O
+//  for each bit of k, most significant first:
+//      R = R + R
+//      if the bit is set: R = R + Q
+//
+// and the bug can strike in any of those point additions. Whether it does depends only on the
+// points being added -- and we pick Q.
+//
+// Say we know the top bits of Bob's key. Then for any Q we can run the ladder ourselves as far as
+// we know it, and see what comes next. If the next bit is 1, Bob adds Q to R; if it's 0, he goes
+// straight on to doubling R. So hunt for a Q whose R + Q trips the bug but whose R + R doesn't,
+// and send it to Bob: if the handshake blows up, the next bit is (probably) 1.
+//
+// "Probably", because the bug might also strike somewhere further down the ladder, in bits we
+// don't know yet. So send a second point too, one that only trips the bug if the next bit is 0.
+// Exactly one of the two is guaranteed to blow up; if both do, something further down the ladder
+// got in the way, and we just try again with fresh points.
+//
+// The curve here is a toy one over a 32 bit prime (with a prime number of points), so that the
+// tests don't spend all day simulating ladders. The attack doesn't care.
+
+use crate::elliptic_curve::{Curve, Point};
+use anyhow::{anyhow, Result};
+use num::bigint::RandBigInt;
+use num::{BigUint, One, Zero};
+
+lazy_static::lazy_static! {
+    // y^2 = x^3 + 728830787x + 715337739 over GF(2948425723)
+    pub static ref CURVE: Curve = Curve::new(
+        2948425723u64.into(),
+        728830787u64.into(),
+        715337739u64.into(),
+    );
+    pub static ref G: Point = Point::new(1151093922u64.into(), 2746365588u64.into());
+    // the order of G, and of the whole curve
+    pub static ref N: BigUint = 2948521267u64.into();
+}
+
+// the bug strikes about once every 2^FAULT_BITS point additions. a real bug would be much rarer,
+// but then so would the points we need to find
+pub const FAULT_BITS: u32 = 6;
+
+/// Bob's bug: when the low bits of x1 * x2 mod p come out all ones, a carry goes missing
+pub fn carry_bug(p1: &Point, p2: &Point) -> bool {
+    match (p1, p2) {
+        (Point::Affine { x: x1, .. }, Point::Affine { x: x2, .. }) => {
+            let mask = BigUint::from((1u32 << FAULT_BITS) - 1);
+            ((x1 * x2) % &CURVE.p) & &mask == mask
+        }
+        _ => false,
+    }
+}
+
+pub struct Bob {
+    private_key: BigUint,
+    pub public_key: Point,
+    pub queries: usize,
+}
+
+impl Bob {
+    pub fn new() -> Bob {
+        let private_key = loop {
+            let k = rand::thread_rng().gen_biguint_below(&N);
+            if !k.is_zero() {
+                break k;
+            }
+        };
+        let public_key = CURVE.scalar_mult(&G, &private_key);
+        Bob {
+            private_key,
+            public_key,
+            queries: 0,
+        }
+    }
+
+    /// Bob's half of ECDH with our public key. all we get to see is whether it blew up
+    pub fn handshake(&mut self, their_public_key: &Point) -> Result<()> {
+        self.queries += 1;
+        if !CURVE.contains(their_public_key) {
+            return Err(anyhow!("that's not a point on the curve"));
+        }
+        CURVE.scalar_mult_with_fault(their_public_key, &self.private_key, &carry_bug)?;
+        Ok(())
+    }
+
+    /// for checking our work, not for the attacker
+    pub fn private_key(&self) -> &BigUint {
+        &self.private_key
+    }
+}
+
+impl Default for Bob {
+    fn default() -> Self {
+        Bob::new()
+    }
+}
+
+fn bits_to_biguint(bits: &[bool]) -> BigUint {
+    bits.iter().fold(BigUint::zero(), |acc, &bit| {
+        (acc << 1u32) + if bit { BigUint::one() } else { BigUint::zero() }
+    })
+}
+
+/// a random Q that gets through the known part of the ladder (and the doubling that comes next)
+/// without tripping the bug, and for which wanted(R, Q) holds, R being where the ladder got to
+fn find_point<F>(known_bits: &[bool], wanted: F) -> Result<Point>
+where
+    F: Fn(&Point, &Point) -> bool,
+{
+    let mut bits = known_bits.to_vec();
+    bits.push(false);
+    loop {
+        let q = CURVE.random_point()?;
+        let Ok(r) = CURVE.double_and_add(&q, &bits, &carry_bug) else {
+            continue;
+        };
+        if wanted(&r, &q) {
+            return Ok(q);
+        }
+    }
+}
+
+fn next_bit(bob: &mut Bob, known_bits: &[bool]) -> Result<bool> {
+    loop {
+        // blows up if Bob adds Q, i.e. if the bit is 1
+        let q1 = find_point(known_bits, |r, q| carry_bug(r, q) && !carry_bug(r, r))?;
+        // blows up if Bob goes straight on to doubling, i.e. if the bit is 0
+        let q0 = find_point(known_bits, |r, q| {
+            let sum = CURVE.add(r, q);
+            carry_bug(r, r) && !carry_bug(r, q) && !carry_bug(&sum, &sum)
+        })?;
+        match (bob.handshake(&q1).is_err(), bob.handshake(&q0).is_err()) {
+            (true, false) => return Ok(true),
+            (false, true) => return Ok(false),
+            // there's no doubling after the last bit, so a 0 there doesn't blow anything up
+            (false, false) => return Ok(false),
+            // the bug struck further down the ladder too. try again
+            (true, true) => continue,
+        }
+    }
+}
+
+/// recovers Bob's private key one bit at a time, from the top. the ladder doesn't do anything
+/// until it meets the leading 1, so that's where we start
+pub fn recover_private_key(bob: &mut Bob) -> Result<BigUint> {
+    let mut bits = vec![true];
+    loop {
+        let candidate = bits_to_biguint(&bits);
+        if CURVE.scalar_mult(&G, &candidate) == bob.public_key {
+            return Ok(candidate);
+        }
+        if bits.len() as u64 >= N.bits() {
+            return Err(anyhow!("ran off the end of the key without finding it"));
+        }
+        bits.push(next_bit(bob, &bits)?);
+    }
+}
+
+/// returns how many handshakes it took
+pub fn attack() -> Result<usize> {
+    assert!(CURVE.scalar_mult(&G, &N).is_infinity());
+    let mut bob = Bob::new();
+    let private_key = recover_private_key(&mut bob)?;
+    assert_eq!(&private_key, bob.private_key());
+    Ok(bob.queries)
+}
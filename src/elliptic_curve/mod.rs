@@ -0,0 +1,196 @@
+pub mod challenge_66;
+
+// Elliptic curves in short Weierstrass form, y^2 = x^3 + ax + b over GF(p), in plain affine
+// coordinates. Textbook formulas, textbook double-and-add, nothing constant time.
+//
+// Point additions take a fault hook: a function of the two points being added that says whether
+// the addition goes wrong. Real code doesn't have one of those on purpose, but real bignum code
+// does occasionally have a carry bug that only shows up for some rare inputs, and that's exactly
+// what the hook lets us play with.
+
+use anyhow::{anyhow, Result};
+use num::bigint::RandBigInt;
+use num::{BigUint, Zero};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Point {
+    Infinity,
+    Affine { x: BigUint, y: BigUint },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Curve {
+    pub p: BigUint,
+    pub a: BigUint,
+    pub b: BigUint,
+}
+
+/// a fault hook that never fires
+pub fn no_fault(_: &Point, _: &Point) -> bool {
+    false
+}
+
+impl Curve {
+    pub fn new(p: BigUint, a: BigUint, b: BigUint) -> Curve {
+        Curve { p, a, b }
+    }
+
+    fn rhs(&self, x: &BigUint) -> BigUint {
+        (x * x * x + &self.a * x + &self.b) % &self.p
+    }
+
+    fn inverse(&self, a: &BigUint) -> BigUint {
+        // fermat: a^(p - 2) = a^-1 mod p
+        a.modpow(&(&self.p - 2u32), &self.p)
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.p - b % &self.p) % &self.p
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        match point {
+            Point::Infinity => true,
+            Point::Affine { x, y } => {
+                x < &self.p && y < &self.p && (y * y) % &self.p == self.rhs(x)
+            }
+        }
+    }
+
+    pub fn negate(&self, point: &Point) -> Point {
+        match point {
+            Point::Infinity => Point::Infinity,
+            Point::Affine { x, y } => Point::Affine {
+                x: x.clone(),
+                y: self.sub(&BigUint::zero(), y),
+            },
+        }
+    }
+
+    /// a uniformly random point other than the point at infinity. only for p = 3 mod 4, where
+    /// square roots are a single exponentiation
+    pub fn random_point(&self) -> Result<Point> {
+        if &self.p % 4u32 != BigUint::from(3u32) {
+            return Err(anyhow!("random_point needs p = 3 mod 4"));
+        }
+        let rng = &mut rand::thread_rng();
+        loop {
+            let x = rng.gen_biguint_below(&self.p);
+            let rhs = self.rhs(&x);
+            let y = rhs.modpow(&((&self.p + 1u32) / 4u32), &self.p);
+            if (&y * &y) % &self.p != rhs {
+                // not a square, so no point has this x
+                continue;
+            }
+            let y = if rand::random() {
+                self.sub(&BigUint::zero(), &y)
+            } else {
+                y
+            };
+            return Ok(Point::Affine { x, y });
+        }
+    }
+
+    pub fn add(&self, p1: &Point, p2: &Point) -> Point {
+        self.add_with_fault(p1, p2, &no_fault).unwrap()
+    }
+
+    /// point addition (and doubling, when p1 == p2). fails if the fault hook fires, which it
+    /// only gets asked about when neither point is the point at infinity
+    pub fn add_with_fault<F>(&self, p1: &Point, p2: &Point, fault: &F) -> Result<Point>
+    where
+        F: Fn(&Point, &Point) -> bool,
+    {
+        let (x1, y1, x2, y2) = match (p1, p2) {
+            (Point::Infinity, _) => return Ok(p2.clone()),
+            (_, Point::Infinity) => return Ok(p1.clone()),
+            (Point::Affine { x: x1, y: y1 }, Point::Affine { x: x2, y: y2 }) => (x1, y1, x2, y2),
+        };
+        if fault(p1, p2) {
+            return Err(anyhow!("fault while adding points"));
+        }
+        if p1 == &self.negate(p2) {
+            return Ok(Point::Infinity);
+        }
+        let slope = if p1 == p2 {
+            // (3x^2 + a) / 2y
+            (3u32 * x1 * x1 + &self.a) * self.inverse(&(2u32 * y1)) % &self.p
+        } else {
+            // (y2 - y1) / (x2 - x1)
+            self.sub(y2, y1) * self.inverse(&self.sub(x2, x1)) % &self.p
+        };
+        let x3 = self.sub(&self.sub(&(&slope * &slope), x1), x2);
+        let y3 = self.sub(&(&slope * self.sub(x1, &x3)), y1);
+        Ok(Point::Affine { x: x3, y: y3 })
+    }
+
+    pub fn scalar_mult(&self, point: &Point, k: &BigUint) -> Point {
+        self.scalar_mult_with_fault(point, k, &no_fault).unwrap()
+    }
+
+    pub fn scalar_mult_with_fault<F>(&self, point: &Point, k: &BigUint, fault: &F) -> Result<Point>
+    where
+        F: Fn(&Point, &Point) -> bool,
+    {
+        let bits: Vec<bool> = (0..k.bits()).rev().map(|i| k.bit(i)).collect();
+        self.double_and_add(point, &bits, fault)
+    }
+
+    /// left to right double-and-add over the bits of the scalar, most significant first:
+    ///
+    ///  R = O
+    ///  for each bit: R = R + R, and if the bit is set, R = R + Q
+    ///
+    /// leading zero bits don't do anything, since nothing gets added to the point at infinity
+    pub fn double_and_add<F>(&self, point: &Point, bits: &[bool], fault: &F) -> Result<Point>
+    where
+        F: Fn(&Point, &Point) -> bool,
+    {
+        let mut result = Point::Infinity;
+        for &bit in bits.iter() {
+            result = self.add_with_fault(&result, &result, fault)?;
+            if bit {
+                result = self.add_with_fault(&result, point, fault)?;
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Point {
+    pub fn new(x: BigUint, y: BigUint) -> Point {
+        Point::Affine { x, y }
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self == &Point::Infinity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_laws() {
+        // y^2 = x^3 + 2x + 3 over GF(103)
+        let curve = Curve::new(103u32.into(), 2u32.into(), 3u32.into());
+        for _ in 0..20 {
+            let p1 = curve.random_point().unwrap();
+            let p2 = curve.random_point().unwrap();
+            let p3 = curve.random_point().unwrap();
+            assert!(curve.contains(&curve.add(&p1, &p2)));
+            assert_eq!(curve.add(&p1, &p2), curve.add(&p2, &p1));
+            assert_eq!(
+                curve.add(&curve.add(&p1, &p2), &p3),
+                curve.add(&p1, &curve.add(&p2, &p3))
+            );
+            assert!(curve.add(&p1, &curve.negate(&p1)).is_infinity());
+            assert_eq!(
+                curve.scalar_mult(&p1, &BigUint::from(3u32)),
+                curve.add(&p1, &curve.add(&p1, &p1))
+            );
+            assert!(curve.scalar_mult(&p1, &BigUint::from(1u32)) == p1);
+        }
+    }
+}
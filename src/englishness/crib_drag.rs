@@ -0,0 +1,310 @@
+// Crib dragging, for when the statistics give up on a many-time pad (challenges 19 and 20).
+//
+// xor two ciphertexts under the same keystream and the keystream cancels: c_i xor c_k is
+// p_i xor p_k. Guess a word (the crib) somewhere in p_i, xor it in, and out comes whatever p_k has
+// at that spot. Slide the crib along every line and look at what falls out of all the others: when
+// it's English everywhere, the guess was probably right. Pin it, and it goes into the shared
+// keystream, which decrypts that column range of every line at once.
+
+use super::scorer::{Scorer, BIGRAMS, UNIGRAMS};
+use anyhow::{anyhow, Result};
+use std::io::{BufRead, Write};
+
+/// the crib at (line, offset), and what it makes every other line say there
+#[derive(Debug, Clone, PartialEq)]
+pub struct CribMatch {
+    pub line: usize,
+    pub offset: usize,
+    /// average per-byte log likelihood of the fragments, higher is more English
+    pub score: f64,
+    /// (other line, what it would say at offset)
+    pub fragments: Vec<(usize, Vec<u8>)>,
+}
+
+pub struct CribDragger {
+    ciphertexts: Vec<Vec<u8>>,
+    keystream: Vec<Option<u8>>,
+}
+
+/// per-byte log likelihood, from the corpus unigrams and bigrams: how English a fragment looks
+pub fn fragment_score(fragment: &[u8]) -> f64 {
+    if fragment.is_empty() {
+        return 0.0;
+    }
+    (UNIGRAMS.score(fragment) + BIGRAMS.score(fragment)) / fragment.len() as f64
+}
+
+impl CribDragger {
+    /// nothing known about the keystream yet
+    pub fn new(ciphertexts: Vec<Vec<u8>>) -> CribDragger {
+        let length = ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0);
+        CribDragger {
+            ciphertexts,
+            keystream: vec![None; length],
+        }
+    }
+
+    /// start from what something else (like the automatic solver) already worked out
+    pub fn with_keystream(ciphertexts: Vec<Vec<u8>>, keystream: &[Option<u8>]) -> CribDragger {
+        let mut dragger = CribDragger::new(ciphertexts);
+        for (slot, &byte) in dragger.keystream.iter_mut().zip(keystream) {
+            *slot = byte;
+        }
+        dragger
+    }
+
+    pub fn lines(&self) -> usize {
+        self.ciphertexts.len()
+    }
+
+    pub fn keystream(&self) -> &[Option<u8>] {
+        &self.keystream
+    }
+
+    /// every place the crib fits, best first
+    pub fn drag(&self, crib: &[u8]) -> Vec<CribMatch> {
+        let mut matches = vec![];
+        if crib.is_empty() {
+            return matches;
+        }
+        for (line, ciphertext) in self.ciphertexts.iter().enumerate() {
+            for offset in 0..(ciphertext.len() + 1).saturating_sub(crib.len()) {
+                let range = offset..offset + crib.len();
+                let fragments = self
+                    .ciphertexts
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other, c)| other != line && c.len() >= range.end)
+                    .map(|(other, c)| {
+                        let fragment = c[range.clone()]
+                            .iter()
+                            .zip(&ciphertext[range.clone()])
+                            .zip(crib)
+                            .map(|((a, b), p)| a ^ b ^ p)
+                            .collect::<Vec<u8>>();
+                        (other, fragment)
+                    })
+                    .collect::<Vec<_>>();
+                if fragments.is_empty() {
+                    continue;
+                }
+                let score = fragments
+                    .iter()
+                    .map(|(_, fragment)| fragment_score(fragment))
+                    .sum::<f64>()
+                    / fragments.len() as f64;
+                matches.push(CribMatch {
+                    line,
+                    offset,
+                    score,
+                    fragments,
+                });
+            }
+        }
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches
+    }
+
+    /// say that line has guess at offset, which fixes that much of the keystream
+    pub fn pin(&mut self, line: usize, offset: usize, guess: &[u8]) -> Result<()> {
+        let ciphertext = self
+            .ciphertexts
+            .get(line)
+            .ok_or_else(|| anyhow!("there's no line {line}"))?;
+        if offset + guess.len() > ciphertext.len() {
+            return Err(anyhow!(
+                "line {line} is only {} bytes long",
+                ciphertext.len()
+            ));
+        }
+        for (i, p) in guess.iter().enumerate() {
+            self.keystream[offset + i] = Some(ciphertext[offset + i] ^ p);
+        }
+        Ok(())
+    }
+
+    /// forget the keystream from offset for length bytes
+    pub fn unpin(&mut self, offset: usize, length: usize) {
+        let end = (offset + length).min(self.keystream.len());
+        for byte in self.keystream[offset.min(end)..end].iter_mut() {
+            *byte = None;
+        }
+    }
+
+    /// every line, decrypted as far as we know the keystream
+    pub fn grid(&self) -> Vec<Vec<Option<u8>>> {
+        self.ciphertexts
+            .iter()
+            .map(|ciphertext| {
+                ciphertext
+                    .iter()
+                    .zip(&self.keystream)
+                    .map(|(c, k)| k.map(|k| c ^ k))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// the grid as text: '_' where we don't know, '?' where it isn't printable
+    pub fn render_grid(&self) -> String {
+        let mut output = String::new();
+        for (line, row) in self.grid().iter().enumerate() {
+            output.push_str(&format!("{line:3} "));
+            for byte in row {
+                output.push(match byte {
+                    None => '_',
+                    Some(b) if (0x20..0x7f).contains(b) => *b as char,
+                    Some(_) => '?',
+                });
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+const HELP: &str = "commands:
+  drag <crib>                 where does this word fit? (top 10)
+  pin <line> <offset> <text>  line has text at offset
+  unpin <offset> <length>     forget some keystream
+  grid                        show what we've got
+  help
+  quit
+";
+
+/// a little text REPL for crib dragging: read commands from input, write to output
+pub fn repl<R: BufRead, W: Write>(
+    dragger: &mut CribDragger,
+    input: R,
+    mut output: W,
+) -> Result<()> {
+    write!(output, "{}", dragger.render_grid())?;
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let (command, rest) = line
+            .trim_start()
+            .split_once(' ')
+            .unwrap_or((line.trim(), ""));
+        match command {
+            "" => {}
+            "quit" | "exit" => break,
+            "help" => write!(output, "{HELP}")?,
+            "grid" => write!(output, "{}", dragger.render_grid())?,
+            "drag" => {
+                for m in dragger.drag(rest.as_bytes()).iter().take(10) {
+                    writeln!(
+                        output,
+                        "line {} offset {} score {:.2}",
+                        m.line, m.offset, m.score
+                    )?;
+                    for (other, fragment) in m.fragments.iter().take(5) {
+                        writeln!(
+                            output,
+                            "    {other:3} {:?}",
+                            String::from_utf8_lossy(fragment)
+                        )?;
+                    }
+                }
+            }
+            "pin" => {
+                let mut parts = rest.splitn(3, ' ');
+                let parsed = match (parts.next(), parts.next(), parts.next()) {
+                    (Some(l), Some(o), Some(text)) => {
+                        l.parse().ok().zip(o.parse().ok()).map(|lo| (lo, text))
+                    }
+                    _ => None,
+                };
+                match parsed {
+                    Some(((l, o), text)) => match dragger.pin(l, o, text.as_bytes()) {
+                        Ok(()) => write!(output, "{}", dragger.render_grid())?,
+                        Err(e) => writeln!(output, "{e}")?,
+                    },
+                    None => writeln!(output, "usage: pin <line> <offset> <text>")?,
+                }
+            }
+            "unpin" => {
+                let numbers = rest
+                    .split_whitespace()
+                    .map(|n| n.parse::<usize>())
+                    .collect::<std::result::Result<Vec<_>, _>>();
+                match numbers.as_deref() {
+                    Ok([offset, length]) => {
+                        dragger.unpin(*offset, *length);
+                        write!(output, "{}", dragger.render_grid())?;
+                    }
+                    _ => writeln!(output, "usage: unpin <offset> <length>")?,
+                }
+            }
+            _ => writeln!(output, "what's {command:?}? try help")?,
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ciphertexts() -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+        let plaintexts = [
+            "A terrible beauty is born.",
+            "I have met them at close of day",
+            "Coming with vivid faces",
+            "From counter or desk among grey",
+            "Eighteenth-century houses.",
+        ]
+        .map(|p| p.as_bytes().to_vec())
+        .to_vec();
+        let keystream = (0..64u8)
+            .map(|i| i.wrapping_mul(151).wrapping_add(17))
+            .collect::<Vec<_>>();
+        let ciphertexts = plaintexts
+            .iter()
+            .map(|p| p.iter().zip(&keystream).map(|(a, b)| a ^ b).collect())
+            .collect();
+        (plaintexts, ciphertexts)
+    }
+
+    #[test]
+    fn test_drag_and_pin() {
+        let (plaintexts, ciphertexts) = ciphertexts();
+        let mut dragger = CribDragger::new(ciphertexts);
+        let best = &dragger.drag(b" terrible ")[0];
+        assert_eq!((best.line, best.offset), (0, 1));
+        assert!(best
+            .fragments
+            .iter()
+            .any(|(other, fragment)| *other == 1 && fragment == b" have met "));
+
+        dragger.pin(0, 0, b"A terrible beauty is born.").unwrap();
+        let grid = dragger.grid();
+        for (row, plaintext) in grid.iter().zip(&plaintexts) {
+            for (i, byte) in row.iter().enumerate() {
+                assert_eq!(*byte, (i < 26).then(|| plaintext[i]));
+            }
+        }
+        assert!(dragger.pin(0, 20, b"too long for the line").is_err());
+        assert!(dragger.pin(9, 0, b"no").is_err());
+        dragger.unpin(2, 100);
+        assert!(dragger.keystream()[..2].iter().all(|k| k.is_some()));
+        assert!(dragger.keystream()[2..].iter().all(|k| k.is_none()));
+    }
+
+    #[test]
+    fn test_repl() {
+        let (_plaintexts, ciphertexts) = ciphertexts();
+        let mut dragger = CribDragger::new(ciphertexts);
+        let input = "drag  terrible \npin 1 0 I have met them\nnonsense\nquit\npin 2 0 ignored\n";
+        let mut output = vec![];
+        repl(&mut dragger, input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("line 0 offset 1"));
+        assert!(output.contains("  2 Coming with viv"));
+        assert!(output.contains("what's \"nonsense\"?"));
+        assert!(dragger.keystream()[16].is_none());
+    }
+}
@@ -0,0 +1,406 @@
+// Scorers for plaintexts that aren't English prose: files with magic numbers at the front, text in
+// a restricted alphabet (base64, hex, JSON and friends are all printable ASCII), UTF-8 in general,
+// and other languages. They're all Scorers, so any attack that takes a scorer can use them.
+
+use super::scorer::Scorer;
+
+/// a file type we can recognise by its first few bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMagic {
+    pub name: &'static str,
+    pub magic: &'static [u8],
+}
+
+pub const FILE_MAGIC: &[FileMagic] = &[
+    FileMagic {
+        name: "png",
+        magic: b"\x89PNG\r\n\x1a\n",
+    },
+    FileMagic {
+        name: "xz",
+        magic: b"\xfd7zXZ\x00",
+    },
+    FileMagic {
+        name: "7z",
+        magic: b"7z\xbc\xaf\x27\x1c",
+    },
+    FileMagic {
+        name: "pdf",
+        magic: b"%PDF-",
+    },
+    FileMagic {
+        name: "zip",
+        magic: b"PK\x03\x04",
+    },
+    FileMagic {
+        name: "elf",
+        magic: b"\x7fELF",
+    },
+    FileMagic {
+        name: "gif",
+        magic: b"GIF8",
+    },
+    FileMagic {
+        name: "bzip2",
+        magic: b"BZh",
+    },
+    FileMagic {
+        name: "jpeg",
+        magic: b"\xff\xd8\xff",
+    },
+    FileMagic {
+        name: "gzip",
+        magic: b"\x1f\x8b\x08",
+    },
+    FileMagic {
+        name: "zlib",
+        magic: b"\x78\x9c",
+    },
+    FileMagic {
+        name: "zlib",
+        magic: b"\x78\xda",
+    },
+    FileMagic {
+        name: "windows executable",
+        magic: b"MZ",
+    },
+];
+
+/// the longest magic number the data starts with
+pub fn detect_file_type(data: &[u8]) -> Option<&'static FileMagic> {
+    FILE_MAGIC
+        .iter()
+        .filter(|file| data.starts_with(file.magic))
+        .max_by_key(|file| file.magic.len())
+}
+
+/// scores how many bytes of a known magic number the data starts with, so the longer (less
+/// likely to be an accident) the better. 0 if it doesn't look like anything
+pub struct MagicScorer;
+
+impl Scorer for MagicScorer {
+    fn score(&self, text: &[u8]) -> f64 {
+        detect_file_type(text).map_or(0.0, |file| file.magic.len() as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    /// space to tilde, plus tab, newline and carriage return
+    PrintableAscii,
+    /// standard or url-safe, with = padding and line breaks
+    Base64,
+    /// either case
+    Hex,
+}
+
+impl Alphabet {
+    pub fn contains(self, byte: u8) -> bool {
+        match self {
+            Alphabet::PrintableAscii => {
+                (0x20..=0x7e).contains(&byte) || matches!(byte, b'\t' | b'\n' | b'\r')
+            }
+            Alphabet::Base64 => {
+                byte.is_ascii_alphanumeric()
+                    || matches!(byte, b'+' | b'/' | b'-' | b'_' | b'=' | b'\n' | b'\r')
+            }
+            Alphabet::Hex => byte.is_ascii_hexdigit() || matches!(byte, b'\n' | b'\r'),
+        }
+    }
+
+    /// how much of the text is in the alphabet, from 0 to 1
+    pub fn fraction(self, text: &[u8]) -> f64 {
+        if text.is_empty() {
+            return 0.0;
+        }
+        text.iter().filter(|&&b| self.contains(b)).count() as f64 / text.len() as f64
+    }
+}
+
+/// the fraction of the text in the alphabet
+impl Scorer for Alphabet {
+    fn score(&self, text: &[u8]) -> f64 {
+        self.fraction(text)
+    }
+}
+
+/// text that base64 decodes, padding and all (line breaks are ignored)
+pub fn is_base64(text: &[u8]) -> bool {
+    let stripped = text
+        .iter()
+        .filter(|&&b| b != b'\n' && b != b'\r')
+        .copied()
+        .collect::<Vec<u8>>();
+    std::str::from_utf8(&stripped)
+        .ok()
+        .and_then(|s| crate::cryptopal_util::b64_to_bytes(s.to_string()).ok())
+        .is_some()
+}
+
+pub fn is_hex(text: &[u8]) -> bool {
+    let text = text.trim_ascii();
+    text.len().is_multiple_of(2) && text.iter().all(|b| b.is_ascii_hexdigit())
+}
+
+/// how much like text UTF-8 is, per byte: letters, digits, punctuation and whitespace in any script
+/// are good, control characters are bad, and bytes that aren't valid UTF-8 are worse
+pub struct Utf8Scorer;
+
+impl Scorer for Utf8Scorer {
+    fn score(&self, text: &[u8]) -> f64 {
+        if text.is_empty() {
+            return 0.0;
+        }
+        let mut score = 0.0;
+        for chunk in text.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                score += if c.is_alphanumeric() || c == ' ' || c == '\n' {
+                    1.0
+                } else if c.is_control() {
+                    -1.0
+                } else {
+                    0.5
+                };
+            }
+            score -= 2.0 * chunk.invalid().len() as f64;
+        }
+        score / text.len() as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+    Spanish,
+}
+
+// letter frequencies in percent, from running text (accented letters counted on their own)
+const ENGLISH_LETTERS: &[(char, f64)] = &[
+    ('a', 8.167),
+    ('b', 1.492),
+    ('c', 2.782),
+    ('d', 4.253),
+    ('e', 12.702),
+    ('f', 2.228),
+    ('g', 2.015),
+    ('h', 6.094),
+    ('i', 6.966),
+    ('j', 0.153),
+    ('k', 0.772),
+    ('l', 4.025),
+    ('m', 2.406),
+    ('n', 6.749),
+    ('o', 7.507),
+    ('p', 1.929),
+    ('q', 0.095),
+    ('r', 5.987),
+    ('s', 6.327),
+    ('t', 9.056),
+    ('u', 2.758),
+    ('v', 0.978),
+    ('w', 2.360),
+    ('x', 0.150),
+    ('y', 1.974),
+    ('z', 0.074),
+];
+
+const GERMAN_LETTERS: &[(char, f64)] = &[
+    ('a', 6.516),
+    ('b', 1.886),
+    ('c', 2.732),
+    ('d', 5.076),
+    ('e', 16.396),
+    ('f', 1.656),
+    ('g', 3.009),
+    ('h', 4.577),
+    ('i', 6.550),
+    ('j', 0.268),
+    ('k', 1.417),
+    ('l', 3.437),
+    ('m', 2.534),
+    ('n', 9.776),
+    ('o', 2.594),
+    ('p', 0.670),
+    ('q', 0.018),
+    ('r', 7.003),
+    ('s', 7.270),
+    ('t', 6.154),
+    ('u', 4.166),
+    ('v', 0.846),
+    ('w', 1.921),
+    ('x', 0.034),
+    ('y', 0.039),
+    ('z', 1.134),
+    ('ä', 0.578),
+    ('ö', 0.443),
+    ('ü', 0.995),
+    ('ß', 0.307),
+];
+
+const SPANISH_LETTERS: &[(char, f64)] = &[
+    ('a', 11.525),
+    ('b', 2.215),
+    ('c', 4.019),
+    ('d', 5.010),
+    ('e', 12.181),
+    ('f', 0.692),
+    ('g', 1.768),
+    ('h', 0.703),
+    ('i', 6.247),
+    ('j', 0.493),
+    ('k', 0.011),
+    ('l', 4.967),
+    ('m', 3.157),
+    ('n', 6.712),
+    ('o', 8.683),
+    ('p', 2.510),
+    ('q', 0.877),
+    ('r', 6.871),
+    ('s', 7.977),
+    ('t', 4.632),
+    ('u', 2.927),
+    ('v', 1.138),
+    ('w', 0.017),
+    ('x', 0.215),
+    ('y', 1.008),
+    ('z', 0.467),
+    ('á', 0.502),
+    ('é', 0.433),
+    ('í', 0.725),
+    ('ñ', 0.311),
+    ('ó', 0.827),
+    ('ú', 0.168),
+    ('ü', 0.012),
+];
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::German, Language::Spanish];
+
+    pub fn letter_frequencies(self) -> &'static [(char, f64)] {
+        match self {
+            Language::English => ENGLISH_LETTERS,
+            Language::German => GERMAN_LETTERS,
+            Language::Spanish => SPANISH_LETTERS,
+        }
+    }
+
+    /// log P(c) for one character of running text
+    pub fn char_log_likelihood(self, c: char) -> f64 {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        let p = match self
+            .letter_frequencies()
+            .iter()
+            .find(|&&(letter, _)| letter == lower)
+        {
+            // a bit of the letters' share goes to capitals
+            Some(&(_, percent)) if c.is_lowercase() => 0.74 * percent / 100.0,
+            Some(&(_, percent)) => 0.04 * percent / 100.0,
+            None if c == ' ' => 0.16,
+            None if c.is_ascii_punctuation() || c.is_ascii_digit() || c == '\n' => 0.002,
+            // letters from some other language's alphabet
+            None if c.is_alphabetic() => 0.00005,
+            None if c.is_control() => 1e-7,
+            None => 0.0002,
+        };
+        p.ln()
+    }
+}
+
+/// log likelihood of the characters of (UTF-8) text in a language. bytes that aren't UTF-8 score
+/// like control characters
+impl Scorer for Language {
+    fn score(&self, text: &[u8]) -> f64 {
+        let mut score = 0.0;
+        for chunk in text.utf8_chunks() {
+            score += chunk
+                .valid()
+                .chars()
+                .map(|c| self.char_log_likelihood(c))
+                .sum::<f64>();
+            score += chunk.invalid().len() as f64 * 1e-7f64.ln();
+        }
+        score
+    }
+
+    fn score_per_unit(&self, text: &[u8]) -> f64 {
+        if text.is_empty() {
+            return f64::NEG_INFINITY;
+        }
+        self.score(text) / text.len() as f64
+    }
+}
+
+/// which of the languages the text is most likely to be in
+pub fn identify_language(text: &[u8]) -> Language {
+    Language::ALL
+        .into_iter()
+        .max_by(|a, b| a.score(text).total_cmp(&b.score(text)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::englishness::{compute_fixed_xor, find_best_fixed_xor};
+
+    const GERMAN: &str = "Die Würde des Menschen ist unantastbar. Sie zu achten und zu schützen ist \
+                          Verpflichtung aller staatlichen Gewalt. Größere Straßen führen über die Brücke.";
+    const SPANISH: &str = "En un lugar de la Mancha, de cuyo nombre no quiero acordarme, no ha mucho \
+                           tiempo que vivía un hidalgo de los de lanza en astillero y adarga antigua.";
+    const ENGLISH: &str = "It was the best of times, it was the worst of times, it was the age of \
+                           wisdom, it was the age of foolishness, it was the epoch of belief.";
+
+    #[test]
+    fn test_languages() {
+        assert_eq!(identify_language(GERMAN.as_bytes()), Language::German);
+        assert_eq!(identify_language(SPANISH.as_bytes()), Language::Spanish);
+        assert_eq!(identify_language(ENGLISH.as_bytes()), Language::English);
+        for (text, language) in [(GERMAN, Language::German), (SPANISH, Language::Spanish)] {
+            let encrypted = compute_fixed_xor(text.as_bytes(), 0x5c);
+            let (key, plaintext, _) = find_best_fixed_xor(&encrypted, &language);
+            assert_eq!(key, 0x5c);
+            assert_eq!(plaintext, text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_utf8() {
+        let encrypted = compute_fixed_xor(GERMAN.as_bytes(), 0x13);
+        let (key, _, _) = find_best_fixed_xor(&encrypted, &Utf8Scorer);
+        // the case bit isn't something UTF-8 alone can see
+        assert!(key == 0x13 || key == 0x13 ^ 0x20);
+        assert!(Utf8Scorer.score("Grüße aus Köln".as_bytes()) > Utf8Scorer.score(b"Gr\xfc\xdfe"));
+    }
+
+    #[test]
+    fn test_magic() {
+        let mut gzip = b"\x1f\x8b\x08\x00\x00\x00\x00\x00".to_vec();
+        gzip.extend(std::iter::repeat_n(0x42, 32));
+        assert_eq!(detect_file_type(&gzip).unwrap().name, "gzip");
+        assert_eq!(detect_file_type(b"%PDF-1.7").unwrap().name, "pdf");
+        assert_eq!(detect_file_type(b"hello"), None);
+        let (key, plaintext, score) =
+            find_best_fixed_xor(&compute_fixed_xor(&gzip, 0xa7), &MagicScorer);
+        assert_eq!(key, 0xa7);
+        assert_eq!(plaintext, gzip);
+        assert_eq!(score, 3.0);
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let base64 = b"SSdtIGtpbGxpbmcgeW91ciBicmFpbg==";
+        let hex = b"49276d206b696c6c696e6720796f7572";
+        assert!(is_base64(base64));
+        assert!(!is_base64(b"SSdtIGtpbGxpbmcgeW91ciBicmFpbg="));
+        assert!(is_hex(hex));
+        assert!(!is_hex(b"49276d2"));
+        assert_eq!(Alphabet::Base64.fraction(base64), 1.0);
+        assert_eq!(Alphabet::Hex.fraction(hex), 1.0);
+        assert!(Alphabet::Hex.fraction(base64) < 1.0);
+        assert_eq!(Alphabet::PrintableAscii.fraction(b"{\"a\": [1, 2]}\n"), 1.0);
+        let (key, _, score) = find_best_fixed_xor(&compute_fixed_xor(hex, 0x77), &Alphabet::Hex);
+        assert_eq!(score, 1.0);
+        assert_eq!(compute_fixed_xor(hex, 0x77 ^ key), hex);
+    }
+}
@@ -0,0 +1,223 @@
+// Challenge 4, for dumps too big to hold: which lines are single byte xored English? Read lines
+// from anything, a batch at a time, break each line on one of several threads, and keep only the
+// best few so far.
+
+use super::find_best_fixed_xor;
+use super::scorer::Scorer;
+use anyhow::{Context, Result};
+use std::io::BufRead;
+
+// enough lines per batch to keep every thread busy, few enough not to care about memory
+const BATCH_LINES: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// counting from 0, blank lines included
+    pub line: usize,
+    pub key: u8,
+    pub plaintext: Vec<u8>,
+    /// per n-gram (or byte), so long lines don't win just for having more of them
+    pub score: f64,
+}
+
+fn detect(line: usize, bytes: &[u8], scorer: &(impl Scorer + ?Sized)) -> Detection {
+    let (key, plaintext, _) = find_best_fixed_xor(bytes, scorer);
+    Detection {
+        line,
+        key,
+        score: scorer.score_per_unit(&plaintext),
+        plaintext,
+    }
+}
+
+// best first, and no more than top of them
+fn keep_best(best: &mut Vec<Detection>, found: Vec<Detection>, top: usize) {
+    best.extend(found);
+    best.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.line.cmp(&b.line)));
+    best.truncate(top);
+}
+
+/// the top lines (each with its best key), best first, using every core. decode turns a line (with
+/// whitespace trimmed) into ciphertext; blank lines are skipped
+pub fn hunt<R, D, S>(reader: R, decode: D, scorer: &S, top: usize) -> Result<Vec<Detection>>
+where
+    R: BufRead,
+    D: Fn(&str) -> Result<Vec<u8>>,
+    S: Scorer + Sync + ?Sized,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    hunt_with_threads(reader, decode, scorer, top, threads)
+}
+
+pub fn hunt_with_threads<R, D, S>(
+    reader: R,
+    decode: D,
+    scorer: &S,
+    top: usize,
+    threads: usize,
+) -> Result<Vec<Detection>>
+where
+    R: BufRead,
+    D: Fn(&str) -> Result<Vec<u8>>,
+    S: Scorer + Sync + ?Sized,
+{
+    let threads = threads.max(1);
+    let mut best = Vec::new();
+    let mut lines = reader.lines().enumerate();
+    loop {
+        let mut read = 0;
+        let mut batch = Vec::new();
+        for (i, line) in lines.by_ref().take(BATCH_LINES) {
+            read += 1;
+            let line = line?;
+            let line = line.trim();
+            if !line.is_empty() {
+                batch.push((i, decode(line).with_context(|| format!("line {i}"))?));
+            }
+        }
+
+        // deal the batch out round robin, one hand per thread
+        let mut hands = vec![Vec::new(); threads.min(batch.len())];
+        for (i, item) in batch.into_iter().enumerate() {
+            hands[i % threads].push(item);
+        }
+        let found = std::thread::scope(|scope| {
+            let handles = hands
+                .into_iter()
+                .map(|hand| {
+                    scope.spawn(move || {
+                        hand.into_iter()
+                            .map(|(line, bytes)| detect(line, &bytes, scorer))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("a hunting thread panicked"))
+                .collect::<Vec<_>>()
+        });
+        keep_best(&mut best, found, top);
+
+        if read < BATCH_LINES {
+            return Ok(best);
+        }
+    }
+}
+
+/// the same, for ciphertexts already in memory, on this thread
+pub fn hunt_in(
+    ciphertexts: &[Vec<u8>],
+    scorer: &(impl Scorer + ?Sized),
+    top: usize,
+) -> Vec<Detection> {
+    let mut best = Vec::new();
+    for (line, bytes) in ciphertexts.iter().enumerate() {
+        keep_best(&mut best, vec![detect(line, bytes, scorer)], top);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptopal_util::{bytes_to_hex, hex_to_bytes, read_lines_from_file};
+    use crate::englishness::compute_fixed_xor;
+    use crate::englishness::scorer::{ChiSquared, QUADGRAMS, UNIGRAMS};
+    use std::io::BufReader;
+
+    fn hex(line: &str) -> Result<Vec<u8>> {
+        hex_to_bytes(line.to_string())
+    }
+
+    #[test]
+    fn test_data_4() {
+        let file = BufReader::new(std::fs::File::open("./data/4.txt").unwrap());
+        let found = hunt(file, hex, &*QUADGRAMS, 5).unwrap();
+        assert_eq!(found.len(), 5);
+        assert_eq!(found[0].plaintext, b"Now that the party is jumping\n");
+        assert_eq!(found[0].key, b'5');
+        assert!(found.windows(2).all(|w| w[0].score >= w[1].score));
+
+        // same answer however many threads, and the same as doing it all in memory
+        let lines = read_lines_from_file("./data/4.txt".to_string()).unwrap();
+        let in_memory = hunt_in(
+            &lines.iter().map(|l| hex(l).unwrap()).collect::<Vec<_>>(),
+            &*QUADGRAMS,
+            5,
+        );
+        for threads in [1, 3] {
+            let file = BufReader::new(std::fs::File::open("./data/4.txt").unwrap());
+            assert_eq!(
+                hunt_with_threads(file, hex, &*QUADGRAMS, 5, threads).unwrap(),
+                found
+            );
+        }
+        assert_eq!(in_memory, found);
+    }
+
+    #[test]
+    fn test_streaming_across_batches() {
+        // noise, with a few english lines (and some blank ones) well past the first batch
+        let mut dump = String::new();
+        let mut seed = 1u32;
+        let mut english = vec![];
+        for i in 0..2100 {
+            if i % 1000 == 999 {
+                dump.push('\n');
+            } else if i % 700 == 699 {
+                let text = format!("line {i} is the one you are looking for");
+                dump.push_str(&bytes_to_hex(&compute_fixed_xor(text.as_bytes(), i as u8)));
+                dump.push('\n');
+                english.push(i);
+            } else {
+                let noise = (0..30)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                        (seed >> 16) as u8
+                    })
+                    .collect::<Vec<_>>();
+                dump.push_str(&bytes_to_hex(&noise));
+                dump.push('\n');
+            }
+        }
+        let found = hunt(dump.as_bytes(), hex, &*UNIGRAMS, english.len()).unwrap();
+        let mut lines = found.iter().map(|d| d.line).collect::<Vec<_>>();
+        lines.sort();
+        assert_eq!(lines, english);
+        assert!(found.iter().all(|d| d.key == d.line as u8));
+    }
+
+    #[test]
+    fn test_mixed_lengths() {
+        // short junk has hardly any n-grams to be penalised for, and long english has plenty
+        let lines = [
+            compute_fixed_xor(b"\x8f\x02\xd3", 0x11),
+            compute_fixed_xor(
+                b"He said the line was long, and the hunt was longer still",
+                0x41,
+            ),
+            compute_fixed_xor(b"\x01\xfe", 0x42),
+            compute_fixed_xor(
+                &(0..60u8)
+                    .map(|i| i.wrapping_mul(37).wrapping_add(5))
+                    .collect::<Vec<_>>(),
+                0x43,
+            ),
+            compute_fixed_xor(b"Short and sweet", 0x44),
+        ];
+        let scorers: [&dyn Scorer; 3] = [&*QUADGRAMS, &*UNIGRAMS, &ChiSquared];
+        for scorer in scorers {
+            let found = hunt_in(&lines, scorer, 2);
+            assert_eq!(found.iter().map(|d| d.line).collect::<Vec<_>>(), [1, 4]);
+            assert_eq!(found[0].key, 0x41);
+        }
+    }
+
+    #[test]
+    fn test_bad_lines() {
+        let error = hunt("00ff\nnot hex\n".as_bytes(), hex, &*UNIGRAMS, 1).unwrap_err();
+        assert!(error.to_string().contains("line 1"));
+        assert!(hunt("".as_bytes(), hex, &*UNIGRAMS, 3).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+// How long is the key of a repeating key xor? Three ways of asking, all of which look for the same
+// thing: bytes a key length apart were xored with the same key byte, so they're as alike as two
+// bytes of plaintext are. Bytes any other distance apart aren't.
+//
+//  - hamming: the average bit difference between every pair of key length blocks (well, the first
+//    few dozen), per bit. Plaintext against plaintext differs in fewer bits than noise does.
+//  - index of coincidence: split the ciphertext into key length columns, each of which is a
+//    single byte xor of plaintext, so has plaintext's IoC. Wrong lengths mix key bytes and flatten
+//    it.
+//  - autocorrelation (Kasiski, more or less): how often a byte matches the one k, 2k, 3k... on.
+//
+// A multiple of the key length looks just as good as the key length by all of these, and scores a
+// bit better or worse by chance. When it's close, the shorter one wins. A breaker should still try
+// a few of the top candidates, and shrink the key to its period afterwards.
+//
+// Each measure is turned into a z-score across all the candidate lengths, so they can be added up
+// without one drowning out the others.
+
+use super::scorer::index_of_coincidence;
+use crate::cryptopal_util::hamming_distance;
+
+/// a reasonable longest key to look for, if you don't know better
+pub const MAX_KEYSIZE: usize = 128;
+
+// no point comparing every block of a long ciphertext: the first few dozen say it all
+const MAX_HAMMING_BLOCKS: usize = 24;
+const AUTOCORRELATION_MULTIPLES: usize = 4;
+// a multiple has fewer, shorter columns than its divisor, so its IoC and autocorrelation come out
+// high by chance more. on a couple of thousand bytes that's worth 15% or so
+const MULTIPLE_MARGIN: f64 = 0.75;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeysizeCandidate {
+    pub keysize: usize,
+    /// higher is likelier, only comparable within one ranking
+    pub score: f64,
+    /// average bit difference between blocks, from 0 to 1, if there were two whole blocks
+    pub hamming: Option<f64>,
+    /// average index of coincidence of the columns
+    pub ioc: f64,
+    /// how often a byte matches one a multiple of keysize on
+    pub autocorrelation: f64,
+}
+
+/// normalized hamming distance between every pair of the first few keysize blocks, per bit. None
+/// if there aren't two blocks
+pub fn all_pairs_hamming(bytes: &[u8], keysize: usize) -> Option<f64> {
+    let blocks = bytes
+        .chunks_exact(keysize)
+        .take(MAX_HAMMING_BLOCKS)
+        .collect::<Vec<_>>();
+    if blocks.len() < 2 {
+        return None;
+    }
+    let mut distance = 0;
+    let mut pairs = 0;
+    for (i, a) in blocks.iter().enumerate() {
+        for b in &blocks[i + 1..] {
+            distance += hamming_distance(a, b);
+            pairs += 1;
+        }
+    }
+    Some(distance as f64 / (pairs * keysize * 8) as f64)
+}
+
+/// average index of coincidence of the keysize columns, leaving out any too short to have one
+pub fn column_ioc(bytes: &[u8], keysize: usize) -> f64 {
+    let columns = (0..keysize)
+        .map(|i| bytes.iter().skip(i).step_by(keysize).copied().collect())
+        .filter(|column: &Vec<u8>| column.len() >= 2)
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        return 0.0;
+    }
+    columns
+        .iter()
+        .map(|column| index_of_coincidence(column))
+        .sum::<f64>()
+        / columns.len() as f64
+}
+
+/// the fraction of bytes that equal the byte shift on from them
+pub fn autocorrelation(bytes: &[u8], shift: usize) -> f64 {
+    if shift == 0 || shift >= bytes.len() {
+        return 0.0;
+    }
+    let matches = bytes
+        .iter()
+        .zip(&bytes[shift..])
+        .filter(|(a, b)| a == b)
+        .count();
+    matches as f64 / (bytes.len() - shift) as f64
+}
+
+fn kasiski(bytes: &[u8], keysize: usize) -> f64 {
+    let shifts = (1..=AUTOCORRELATION_MULTIPLES)
+        .map(|m| m * keysize)
+        .filter(|&shift| shift < bytes.len())
+        .collect::<Vec<_>>();
+    if shifts.is_empty() {
+        return 0.0;
+    }
+    shifts
+        .iter()
+        .map(|&shift| autocorrelation(bytes, shift))
+        .sum::<f64>()
+        / shifts.len() as f64
+}
+
+// how many standard deviations each value is above the mean. all zero if they're all the same
+fn z_scores(values: &[f64]) -> Vec<f64> {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    if variance == 0.0 {
+        return vec![0.0; values.len()];
+    }
+    values
+        .iter()
+        .map(|v| (v - mean) / variance.sqrt())
+        .collect()
+}
+
+/// every key length from 1 to max_keysize (or as far as there's enough ciphertext to have two
+/// bytes per column), likeliest first. empty for empty input
+pub fn rank_keysizes(bytes: &[u8], max_keysize: usize) -> Vec<KeysizeCandidate> {
+    let max_keysize = max_keysize.min(bytes.len() / 2).max(1);
+    if bytes.is_empty() {
+        return vec![];
+    }
+    let mut candidates = (1..=max_keysize)
+        .map(|keysize| KeysizeCandidate {
+            keysize,
+            score: 0.0,
+            hamming: all_pairs_hamming(bytes, keysize),
+            ioc: column_ioc(bytes, keysize),
+            autocorrelation: kasiski(bytes, keysize),
+        })
+        .collect::<Vec<_>>();
+
+    // lengths with no hamming distance (only one block) just don't get that part of the score
+    let with_hamming = candidates
+        .iter()
+        .filter_map(|c| c.hamming)
+        .collect::<Vec<_>>();
+    let mut hamming_z = z_scores(&with_hamming).into_iter();
+    let ioc_z = z_scores(&candidates.iter().map(|c| c.ioc).collect::<Vec<_>>());
+    let autocorrelation_z = z_scores(
+        &candidates
+            .iter()
+            .map(|c| c.autocorrelation)
+            .collect::<Vec<_>>(),
+    );
+    for (i, candidate) in candidates.iter_mut().enumerate() {
+        // closer blocks are better
+        let hamming = candidate
+            .hamming
+            .map_or(0.0, |_| -hamming_z.next().unwrap());
+        candidate.score = hamming + ioc_z[i] + autocorrelation_z[i];
+    }
+    // a multiple that only beats its divisor by a bit is noise: it ties with the divisor instead
+    let scores = candidates.iter().map(|c| c.score).collect::<Vec<_>>();
+    for candidate in candidates.iter_mut().filter(|c| c.score > 0.0) {
+        let divisor_score = (2..candidate.keysize)
+            .filter(|d| candidate.keysize.is_multiple_of(*d))
+            .map(|d| scores[d - 1])
+            .find(|&score| score >= MULTIPLE_MARGIN * candidate.score);
+        if let Some(score) = divisor_score {
+            candidate.score = candidate.score.min(score);
+        }
+    }
+    // ties go to the shorter key, which puts multiples right behind what they're multiples of
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.keysize.cmp(&b.keysize)));
+    candidates
+}
+
+/// the shortest prefix the key is a repeat of (so a key found at a multiple of the real length
+/// comes back to the real one)
+pub fn shortest_period(key: &[u8]) -> &[u8] {
+    (1..key.len())
+        .find(|&period| {
+            key.len().is_multiple_of(period) && key.iter().zip(&key[period..]).all(|(a, b)| a == b)
+        })
+        .map_or(key, |period| &key[..period])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptopal_util::{read_bytes_from_b64_file, repeating_key_xor};
+    use crate::englishness::scorer::{english_pdf, ENGLISH_CORPUS};
+
+    #[test]
+    fn test_rank_challenge_6() {
+        let bytes = read_bytes_from_b64_file("./data/6.txt".to_string()).unwrap();
+        let ranked = rank_keysizes(&bytes, MAX_KEYSIZE);
+        assert_eq!(ranked[0].keysize, 29);
+        assert_eq!(ranked[1].keysize, 58);
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn test_long_keys() {
+        let key = (0..53u8)
+            .map(|i| i.wrapping_mul(97) ^ 0x5a)
+            .collect::<Vec<_>>();
+        let ciphertext = repeating_key_xor(&ENGLISH_CORPUS[..4000], &key);
+        let ranked = rank_keysizes(&ciphertext, MAX_KEYSIZE);
+        assert_eq!(ranked[0].keysize, 53);
+    }
+
+    #[test]
+    fn test_multiples_of_a_longer_key() {
+        // 7 * 18 scores about 15% higher than 18 on this
+        let ciphertext = repeating_key_xor(&english_pdf(), b"another longer key");
+        let ranked = rank_keysizes(&ciphertext, MAX_KEYSIZE);
+        assert_eq!(ranked[0].keysize, 18);
+    }
+
+    #[test]
+    fn test_short_ciphertexts() {
+        assert!(rank_keysizes(b"", MAX_KEYSIZE).is_empty());
+        assert_eq!(rank_keysizes(b"a", MAX_KEYSIZE)[0].keysize, 1);
+        let ranked = rank_keysizes(b"short thing", MAX_KEYSIZE);
+        assert_eq!(ranked.len(), 5);
+        assert!(ranked.iter().all(|c| c.score.is_finite()));
+        assert_eq!(all_pairs_hamming(b"abc", 2), None);
+    }
+
+    #[test]
+    fn test_shortest_period() {
+        assert_eq!(shortest_period(b"abcabcabc"), b"abc");
+        assert_eq!(shortest_period(b"abcab"), b"abcab");
+        assert_eq!(shortest_period(b"aaaa"), b"a");
+        assert_eq!(shortest_period(b""), b"");
+    }
+}
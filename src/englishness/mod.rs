@@ -0,0 +1,120 @@
+pub mod crib_drag;
+pub mod detectors;
+pub mod hunt;
+pub mod keysize;
+pub mod scorer;
+
+use keysize::{rank_keysizes, shortest_period, MAX_KEYSIZE};
+use scorer::Scorer;
+
+pub fn compute_fixed_xor(bytes: &[u8], key: u8) -> Vec<u8> {
+    let mut xor_bytes = Vec::new();
+    for b in bytes.iter() {
+        xor_bytes.push(b ^ key);
+    }
+    xor_bytes
+}
+
+/// returns (key, plaintext, score), for the key whose plaintext the scorer likes best
+pub fn find_best_fixed_xor<S: Scorer + ?Sized>(bytes: &[u8], scorer: &S) -> (u8, Vec<u8>, f64) {
+    let mut best_score = f64::NEG_INFINITY;
+    let mut best_bytes = Vec::new();
+    let mut best_key = 0;
+    for key in 0..=255 {
+        let xor_bytes = compute_fixed_xor(bytes, key);
+        let score = scorer.score(xor_bytes.as_slice());
+        if score.total_cmp(&best_score).is_gt() {
+            best_score = score;
+            best_bytes = xor_bytes;
+            best_key = key;
+        };
+    }
+    (best_key, best_bytes, best_score)
+}
+
+fn transpose_bytes(bytes: &[u8], keysize: usize) -> Vec<Vec<u8>> {
+    let vecs_to_transpose: Vec<Vec<u8>> =
+        bytes.chunks(keysize).map(|chunk| chunk.to_vec()).collect();
+    let mut transposed_bytes = Vec::new();
+    for i in 0..keysize {
+        let mut transposed_vec = Vec::new();
+        for vec in vecs_to_transpose.iter() {
+            if vec.len() > i {
+                transposed_vec.push(vec[i]);
+            } else {
+                break;
+            }
+        }
+        transposed_bytes.push(transposed_vec);
+    }
+    transposed_bytes
+}
+
+fn untranspose_bytes(bytes: &[Vec<u8>]) -> Vec<u8> {
+    let rows = bytes.first().map_or(0, |column| column.len());
+    let mut untransposed_bytes = Vec::new();
+    for i in 0..rows {
+        // the last row can be short
+        untransposed_bytes.extend(bytes.iter().filter_map(|column| column.get(i)));
+    }
+    untransposed_bytes
+}
+
+/// the scorer sees each column on its own, which isn't contiguous text: use unigrams or
+/// chi-squared, not something that cares about neighbours
+pub fn break_repeating_key_xor_with_keysize<S: Scorer + ?Sized>(
+    bytes: Vec<u8>,
+    keysize: usize,
+    scorer: &S,
+) -> (f64, Vec<u8>, Vec<u8>) {
+    let mut key = Vec::new();
+    let transposed = transpose_bytes(&bytes, keysize);
+    let mut transposed_decrypted = Vec::new();
+    // break each transposed chunk into a single byte key
+    for thing_to_break in transposed.iter() {
+        let (key_byte, output, _score) = find_best_fixed_xor(thing_to_break, scorer);
+        key.push(key_byte);
+        transposed_decrypted.push(output);
+    }
+    let untransposed = untranspose_bytes(&transposed_decrypted);
+    let score = scorer.score(untransposed.as_slice());
+    (score, key, untransposed)
+}
+
+// how many of the likeliest key lengths to actually try
+const KEYSIZE_CANDIDATES: usize = 5;
+
+/// returns (key, plaintext)
+pub fn break_repeating_key_xor<S: Scorer + ?Sized>(
+    bytes: Vec<u8>,
+    scorer: &S,
+) -> (Vec<u8>, Vec<u8>) {
+    let mut best_score = f64::NEG_INFINITY;
+    let mut best_key = Vec::new();
+    let mut best_plaintext = Vec::new();
+    let mut tried: Vec<usize> = Vec::new();
+    for candidate in rank_keysizes(&bytes, MAX_KEYSIZE) {
+        // a multiple of a length we've tried only has more room to overfit
+        if tried
+            .iter()
+            .any(|t| candidate.keysize.is_multiple_of(*t) && *t > 1)
+        {
+            continue;
+        }
+        if tried.len() == KEYSIZE_CANDIDATES {
+            break;
+        }
+        tried.push(candidate.keysize);
+        let key_size = candidate.keysize;
+        let (score, key_bytes, plaintext) =
+            break_repeating_key_xor_with_keysize(bytes.clone(), key_size, scorer);
+        if score.total_cmp(&best_score).is_gt() {
+            best_score = score;
+            best_key = key_bytes;
+            best_plaintext = plaintext;
+        }
+    }
+    // a multiple of the key length breaks into the key repeated
+    let best_key = shortest_period(&best_key).to_vec();
+    (best_key, best_plaintext)
+}
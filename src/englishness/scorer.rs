@@ -0,0 +1,266 @@
+// Ways of saying how much a piece of text looks like English, as one number: higher is more
+// English. It's an f64 and compares with total_cmp, so "best" always means something.
+//
+//  - NgramModel: log likelihood of every n-gram in the text (overlapping), with the probabilities
+//    counted from a corpus. Unigrams are all you can use on text that isn't contiguous (a column
+//    of a repeating key xor); quadgrams are much pickier when it is.
+//  - ChiSquared: how far the letter (and space, and everything else) counts are from what
+//    English would give, negated.
+//  - IndexOfCoincidence: how far the chance of two random bytes matching is from English's. That
+//    doesn't change when you xor with a single byte, so it's no good for picking a key, but it is
+//    good for telling whether you've lined up the columns right (key lengths).
+
+use super::detectors::Language;
+use anyhow::Result;
+use std::collections::HashMap;
+
+pub trait Scorer {
+    /// higher is more like English
+    fn score(&self, text: &[u8]) -> f64;
+
+    /// the score per n-gram (or byte, or whatever gets added up), for ranking texts of different
+    /// lengths against each other. text with nothing to add up is as unlike English as it gets.
+    /// scores that don't grow with the text can leave this alone
+    fn score_per_unit(&self, text: &[u8]) -> f64 {
+        self.score(text)
+    }
+}
+
+impl<S: Scorer + ?Sized> Scorer for &S {
+    fn score(&self, text: &[u8]) -> f64 {
+        (**self).score(text)
+    }
+
+    fn score_per_unit(&self, text: &[u8]) -> f64 {
+        (**self).score_per_unit(text)
+    }
+}
+
+pub const ENGLISH_CORPUS: &[u8] = include_bytes!("../../data/english.txt");
+
+/// a couple of thousand bytes of the corpus wrapped up as a PDF, for tests that want a plaintext
+/// that isn't all prose
+#[cfg(test)]
+pub(crate) fn english_pdf() -> Vec<u8> {
+    let mut pdf = b"%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n".to_vec();
+    pdf.extend_from_slice(b"2 0 obj\n<< /Length 2000 >>\nstream\n");
+    pdf.extend_from_slice(&ENGLISH_CORPUS[..2000]);
+    pdf.extend_from_slice(b"\nendstream\nendobj\n%%EOF\n");
+    pdf
+}
+
+lazy_static::lazy_static! {
+    pub static ref UNIGRAMS: NgramModel = NgramModel::from_text(ENGLISH_CORPUS, 1);
+    pub static ref BIGRAMS: NgramModel = NgramModel::from_text(ENGLISH_CORPUS, 2);
+    pub static ref TRIGRAMS: NgramModel = NgramModel::from_text(ENGLISH_CORPUS, 3);
+    pub static ref QUADGRAMS: NgramModel = NgramModel::from_text(ENGLISH_CORPUS, 4);
+}
+
+pub struct NgramModel {
+    n: usize,
+    log_probabilities: HashMap<Vec<u8>, f64>,
+    // for n-grams the corpus never had: as if we'd seen them a hundredth of a time
+    floor: f64,
+}
+
+impl NgramModel {
+    pub fn from_text(corpus: &[u8], n: usize) -> NgramModel {
+        assert!(n > 0, "n-grams need an n");
+        let mut counts: HashMap<Vec<u8>, usize> = HashMap::new();
+        for gram in corpus.windows(n) {
+            *counts.entry(gram.to_vec()).or_default() += 1;
+        }
+        let total = corpus.len().saturating_sub(n - 1).max(1) as f64;
+        NgramModel {
+            n,
+            log_probabilities: counts
+                .into_iter()
+                .map(|(gram, count)| (gram, (count as f64 / total).ln()))
+                .collect(),
+            floor: (0.01 / total).ln(),
+        }
+    }
+
+    pub fn from_file(filename: &str, n: usize) -> Result<NgramModel> {
+        Ok(NgramModel::from_text(&std::fs::read(filename)?, n))
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    pub fn log_probability(&self, gram: &[u8]) -> f64 {
+        *self.log_probabilities.get(gram).unwrap_or(&self.floor)
+    }
+}
+
+impl Scorer for NgramModel {
+    /// text shorter than n has no n-grams, and scores 0
+    fn score(&self, text: &[u8]) -> f64 {
+        text.windows(self.n)
+            .map(|gram| self.log_probability(gram))
+            .sum()
+    }
+
+    fn score_per_unit(&self, text: &[u8]) -> f64 {
+        match text.len().checked_sub(self.n - 1) {
+            Some(grams) if grams > 0 => self.score(text) / grams as f64,
+            _ => f64::NEG_INFINITY,
+        }
+    }
+}
+
+pub struct ChiSquared;
+
+impl ChiSquared {
+    // letters (folded to uppercase), then space, other printable, not printable
+    fn expected_proportions() -> [f64; 29] {
+        let mut expected = [0.0; 29];
+        for &(letter, percent) in Language::English.letter_frequencies() {
+            expected[ChiSquared::category(letter as u8)] = 0.78 * percent / 100.0;
+        }
+        expected[26] = 0.17;
+        expected[27] = 0.05;
+        expected[28] = 0.0001;
+        expected
+    }
+
+    fn category(byte: u8) -> usize {
+        match byte {
+            b'a'..=b'z' => (byte - b'a') as usize,
+            b'A'..=b'Z' => (byte - b'A') as usize,
+            b' ' => 26,
+            b'\n' | b'\t' | b'\r' | 0x21..=0x7e => 27,
+            _ => 28,
+        }
+    }
+
+    pub fn statistic(text: &[u8]) -> f64 {
+        let mut observed = [0usize; 29];
+        for &byte in text {
+            observed[ChiSquared::category(byte)] += 1;
+        }
+        ChiSquared::expected_proportions()
+            .iter()
+            .zip(observed)
+            .map(|(p, count)| {
+                let expected = p * text.len() as f64;
+                (count as f64 - expected).powi(2) / expected
+            })
+            .sum()
+    }
+}
+
+impl Scorer for ChiSquared {
+    fn score(&self, text: &[u8]) -> f64 {
+        if text.is_empty() {
+            return 0.0;
+        }
+        -ChiSquared::statistic(text)
+    }
+
+    fn score_per_unit(&self, text: &[u8]) -> f64 {
+        if text.is_empty() {
+            return f64::NEG_INFINITY;
+        }
+        -ChiSquared::statistic(text) / text.len() as f64
+    }
+}
+
+/// the chance that two bytes picked from text (without replacement) are the same
+pub fn index_of_coincidence(text: &[u8]) -> f64 {
+    if text.len() < 2 {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for &byte in text {
+        counts[byte as usize] += 1;
+    }
+    let matches: usize = counts.iter().map(|&n| n * n.saturating_sub(1)).sum();
+    matches as f64 / (text.len() * (text.len() - 1)) as f64
+}
+
+pub struct IndexOfCoincidence {
+    pub target: f64,
+}
+
+impl IndexOfCoincidence {
+    /// aiming for the corpus's index of coincidence
+    pub fn english() -> IndexOfCoincidence {
+        IndexOfCoincidence {
+            target: index_of_coincidence(ENGLISH_CORPUS),
+        }
+    }
+}
+
+impl Scorer for IndexOfCoincidence {
+    fn score(&self, text: &[u8]) -> f64 {
+        -(index_of_coincidence(text) - self.target).abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::englishness::{break_repeating_key_xor, compute_fixed_xor};
+
+    const ENGLISH: &[u8] = b"Now that the party is jumping, cooking MC's like a pound of bacon";
+
+    #[test]
+    fn test_scorers_prefer_english() {
+        let garbled = compute_fixed_xor(ENGLISH, 0x20 ^ 0x01);
+        let scorers: [&dyn Scorer; 5] =
+            [&*UNIGRAMS, &*BIGRAMS, &*TRIGRAMS, &*QUADGRAMS, &ChiSquared];
+        for scorer in scorers {
+            assert!(scorer.score(ENGLISH) > scorer.score(&garbled));
+            // and within the reach of one byte xor, english is the best
+            let best = (0..=255u8)
+                .max_by(|&a, &b| {
+                    let a = scorer.score(&compute_fixed_xor(ENGLISH, a));
+                    let b = scorer.score(&compute_fixed_xor(ENGLISH, b));
+                    a.total_cmp(&b)
+                })
+                .unwrap();
+            assert_eq!(best, 0);
+        }
+    }
+
+    #[test]
+    fn test_break_repeating_key_xor_with_any_column_scorer() {
+        let bytes =
+            crate::cryptopal_util::read_bytes_from_b64_file("./data/6.txt".to_string()).unwrap();
+        let scorers: [&dyn Scorer; 2] = [&*UNIGRAMS, &ChiSquared];
+        for scorer in scorers {
+            let (key, _plaintext) = break_repeating_key_xor(bytes.clone(), scorer);
+            assert_eq!(key, b"Terminator X: Bring the noise");
+        }
+    }
+
+    #[test]
+    fn test_ngram_model() {
+        let model = NgramModel::from_text(b"abab", 2);
+        assert_eq!(model.n(), 2);
+        assert!((model.log_probability(b"ab") - (2.0f64 / 3.0).ln()).abs() < 1e-12);
+        assert!((model.log_probability(b"ba") - (1.0f64 / 3.0).ln()).abs() < 1e-12);
+        assert!(model.log_probability(b"zz") < model.log_probability(b"ba"));
+        assert_eq!(model.score(b"a"), 0.0);
+        assert_eq!(model.score_per_unit(b"a"), f64::NEG_INFINITY);
+        assert_eq!(model.score_per_unit(b"abab"), model.score(b"abab") / 3.0);
+        assert!(NgramModel::from_file("./data/english.txt", 3).is_ok());
+        assert!(NgramModel::from_file("./data/nope.txt", 3).is_err());
+    }
+
+    #[test]
+    fn test_index_of_coincidence() {
+        assert_eq!(index_of_coincidence(b"aaaa"), 1.0);
+        assert_eq!(index_of_coincidence(b"abcd"), 0.0);
+        let ioc = IndexOfCoincidence::english();
+        // english bytes (spaces and all) sit somewhere around 0.06 to 0.08
+        assert!(ioc.target > 0.05 && ioc.target < 0.09);
+        // xor doesn't change it
+        let xored = compute_fixed_xor(ENGLISH, 0x42);
+        assert_eq!(ioc.score(ENGLISH), ioc.score(&xored));
+        let uniform = (0..=255u8).collect::<Vec<_>>();
+        assert!(ioc.score(ENGLISH) > ioc.score(&uniform));
+    }
+}
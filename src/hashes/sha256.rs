@@ -13,6 +13,10 @@ pub fn sha256(msg: &[u8]) -> String {
         .collect()
 }
 
+pub fn sha256_bytes(msg: &[u8]) -> [u8; 32] {
+    Sha256::digest(msg).into()
+}
+
 pub fn hmac_sha256(key: &[u8], msg: &[u8]) -> [u8; 20] {
     let mut hmaccer = Hmac::<Sha256>::new_from_slice(key).unwrap();
     hmaccer.update(msg);
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+// k=v&k=v cookies, like challenge 13's profiles. Keys and values can hold anything: '%', '&' and
+// '=' get percent-escaped on the way out, so nobody can sneak in an extra "&role=admin" through
+// their email address. (That doesn't stop anyone pasting ECB blocks together, of course.)
+
+pub fn escape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '%' => output.push_str("%25"),
+            '&' => output.push_str("%26"),
+            '=' => output.push_str("%3D"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+pub fn unescape(input: &str) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(i) = rest.find('%') {
+        output.push_str(&rest[..i]);
+        let escaped = rest
+            .get(i..i + 3)
+            .ok_or_else(|| anyhow!("truncated escape in {input:?}"))?;
+        output.push(match escaped {
+            "%25" => '%',
+            "%26" => '&',
+            "%3D" | "%3d" => '=',
+            _ => return Err(anyhow!("unknown escape {escaped:?} in {input:?}")),
+        });
+        rest = &rest[i + 3..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+pub fn encode(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", escape(key), escape(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// the pairs in order
+pub fn parse(input: &str) -> Result<Vec<(String, String)>> {
+    input
+        .split('&')
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("no '=' in {pair:?}"))?;
+            if value.contains('=') {
+                return Err(anyhow!("more than one '=' in {pair:?}"));
+            }
+            Ok((unescape(key)?, unescape(value)?))
+        })
+        .collect()
+}
+
+/// like parse, but refuses to pick between two values for the same key
+pub fn parse_map(input: &str) -> Result<HashMap<String, String>> {
+    let mut output = HashMap::new();
+    for (key, value) in parse(input)? {
+        if output.contains_key(&key) {
+            return Err(anyhow!("{key:?} appears more than once"));
+        }
+        output.insert(key, value);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parsed = parse_map("foo=bar&baz=qux&zap=zazzle").unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed["foo"], "bar");
+        assert_eq!(parsed["baz"], "qux");
+        assert_eq!(parsed["zap"], "zazzle");
+        assert!(parse("foo=bar&baz").is_err());
+        assert!(parse("foo=bar=baz").is_err());
+        assert!(parse("foo=%2").is_err());
+        assert!(parse("foo=%41").is_err());
+        assert!(parse_map("role=user&role=admin").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pairs = [
+            ("email", "foo@bar.com&role=admin"),
+            ("uid", "10"),
+            ("weird=key", "100%"),
+        ];
+        let encoded = encode(&pairs);
+        assert_eq!(
+            encoded,
+            "email=foo@bar.com%26role%3Dadmin&uid=10&weird%3Dkey=100%25"
+        );
+        let parsed = parse(&encoded).unwrap();
+        assert_eq!(parsed.len(), 3);
+        for ((key, value), (parsed_key, parsed_value)) in pairs.iter().zip(&parsed) {
+            assert_eq!(key, parsed_key);
+            assert_eq!(value, parsed_value);
+        }
+    }
+}
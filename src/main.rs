@@ -4,16 +4,31 @@
 pub mod aes_fun;
 pub mod cryptopal_util;
 pub mod diffie_hellman;
+pub mod elliptic_curve;
 pub mod englishness;
+pub mod kv;
 pub mod mersenne_twister;
 pub mod pkcs7;
 pub mod random_things;
 pub mod rsa;
 pub mod srp;
+pub mod xor;
 
 mod hashes;
 mod sets;
 
-fn main() {
-    println!("Hello, world! try running the tests :)");
+fn main() -> anyhow::Result<()> {
+    let args = std::env::args().collect::<Vec<String>>();
+    match args.get(1).map(String::as_str) {
+        Some("crib-drag") => {
+            let filename = args.get(2).map_or("./data/19.txt", String::as_str);
+            let mut dragger = aes_fun::challenge_19_and_20::crib_drag_session(filename)?;
+            englishness::crib_drag::repl(&mut dragger, std::io::stdin().lock(), std::io::stdout())
+        }
+        _ => {
+            println!("Hello, world! try running the tests :)");
+            println!("or drag some cribs: cargo run -- crib-drag [base64 lines file]");
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,634 @@
+// The classic ways RSA keys fall over when they were made badly.
+//
+//  - Fermat: if p and q are close, n = a^2 - b^2 for a just above sqrt(n) and a small b, and then
+//    p, q = a + b, a - b. Walk a up from sqrt(n) until a^2 - n is a square.
+//  - Pollard p - 1: if p - 1 only has small factors, it divides M = lcm(1..bound), so
+//    a^M = 1 mod p (Fermat's little theorem) and gcd(a^M - 1, n) finds p.
+//  - Pollard rho: x -> x^2 + c mod n, looked at mod p, repeats after about sqrt(p) steps. Floyd's
+//    tortoise and hare notice when gcd(x - y, n) stops being 1. Only good for a small factor.
+//  - Wiener: if d < n^(1/4) / 3, then k/d is one of the continued fraction convergents of e/n
+//    (since e*d - k*phi = 1 and phi is close to n). Each convergent gives a guess at phi, and
+//    phi gives p and q as the roots of x^2 - (n - phi + 1)x + n.
+//  - common modulus: the same m encrypted under one n with coprime e1 and e2. Find a*e1 + b*e2 = 1
+//    and c1^a * c2^b = m. No factoring at all.
+//  - batch GCD: keys made with bad randomness share primes. gcd(n_i, product of all the others)
+//    finds them, and a product tree and a remainder tree (Bernstein, as used by Heninger et al. on
+//    millions of keys) do that for every key without dividing by each n_i separately.
+//  - Hastad's broadcast (challenge 40, for any e): the same m encrypted with e keys that all have
+//    public exponent e. The CRT turns the c_i into m^e mod the product of the n_i, but m^e is
+//    smaller than that product, so it's just m^e, and its e-th root is m.
+//  - Hastad's broadcast with known affine padding: each key got a_i * m + b_i instead. Combine
+//    (a_i x + b_i)^e - c_i into one polynomial g with the CRT, so g(m) = 0 mod N, the product of
+//    the moduli, and m is a small root of it (see coppersmith.rs). Smaller than N^(1 / e), in
+//    theory, so e + 1 keys should do. e (e + 1) / 2 of them make for a quick lattice.
+//  - Franklin-Reiter: one key, and two messages where m2 = a * m1 + b for a known a and b. m1 is
+//    a root of both x^e - c1 and (a x + b)^e - c2, so x - m1 divides their gcd, and almost always
+//    is it. The gcd is Euclid's algorithm on polynomials mod n (polynomial.rs), which is
+//    e^2-ish work, so this is for small e.
+//
+// The factoring ones give back (p, q) with p > q, like RsaKey::from_private_exponent.
+
+use super::coppersmith::{affine_power, small_roots};
+use super::polynomial::gcd;
+use super::{crt, egcd, exact_root, invmod, RsaKey, RsaPubKey};
+use anyhow::{anyhow, Result};
+use num::{BigInt, BigUint, Integer, One, Signed, Zero};
+
+// how often Pollard p - 1 stops to take a gcd
+const GCD_EVERY: u64 = 64;
+
+fn ordered(n: &BigUint, factor: BigUint) -> (BigUint, BigUint) {
+    let other = n / &factor;
+    if factor > other {
+        (factor, other)
+    } else {
+        (other, factor)
+    }
+}
+
+// a factor other than 1 and n
+fn nontrivial(n: &BigUint, g: BigUint) -> Option<(BigUint, BigUint)> {
+    (!g.is_one() && &g != n).then(|| ordered(n, g))
+}
+
+/// for p and q within about max_steps * 2 * sqrt(sqrt(n)) of each other (or even n)
+pub fn fermat(n: &BigUint, max_steps: u64) -> Option<(BigUint, BigUint)> {
+    if n.is_even() {
+        return nontrivial(n, BigUint::from(2u32));
+    }
+    let mut a = n.sqrt();
+    if &a * &a < *n {
+        a += 1u32;
+    }
+    for _ in 0..max_steps {
+        if let Some(b) = exact_root(&(&a * &a - n), 2) {
+            return nontrivial(n, &a + b);
+        }
+        a += 1u32;
+    }
+    None
+}
+
+/// finds p if every prime power dividing p - 1 is at most bound
+pub fn pollard_p_minus_1(n: &BigUint, bound: u64) -> Option<(BigUint, BigUint)> {
+    let mut a = BigUint::from(2u32);
+    for j in 2..=bound {
+        a = a.modpow(&BigUint::from(j), n);
+        if j % GCD_EVERY == 0 || j == bound {
+            let g = (&a + n - 1u32).gcd(n);
+            if &g == n {
+                // every factor at once: the bound is too big for this n
+                return None;
+            }
+            if !g.is_one() {
+                return nontrivial(n, g);
+            }
+        }
+    }
+    None
+}
+
+/// finds a factor p in about sqrt(p) steps, if that's less than max_steps
+pub fn pollard_rho(n: &BigUint, max_steps: u64) -> Option<(BigUint, BigUint)> {
+    if n.is_even() {
+        return nontrivial(n, BigUint::from(2u32));
+    }
+    // a different c if the cycle mod p and mod q close at the same time
+    for c in 1u32..10 {
+        let f = |x: &BigUint| (x * x + c) % n;
+        let mut tortoise = BigUint::from(2u32);
+        let mut hare = BigUint::from(2u32);
+        for _ in 0..max_steps {
+            tortoise = f(&tortoise);
+            hare = f(&f(&hare));
+            let difference = if tortoise > hare {
+                &tortoise - &hare
+            } else {
+                &hare - &tortoise
+            };
+            let g = difference.gcd(n);
+            if &g == n {
+                break;
+            }
+            if !g.is_one() {
+                return nontrivial(n, g);
+            }
+        }
+    }
+    None
+}
+
+// e/n as a continued fraction, and the convergents h/k that come from it
+fn convergents(numerator: &BigUint, denominator: &BigUint) -> Vec<(BigUint, BigUint)> {
+    let (mut a, mut b) = (numerator.clone(), denominator.clone());
+    let (mut h, mut h_prev) = (BigUint::one(), BigUint::zero());
+    let (mut k, mut k_prev) = (BigUint::zero(), BigUint::one());
+    let mut out = vec![];
+    while !b.is_zero() {
+        let (quotient, remainder) = a.div_rem(&b);
+        (h, h_prev) = (&quotient * &h + &h_prev, h);
+        (k, k_prev) = (&quotient * &k + &k_prev, k);
+        out.push((h.clone(), k.clone()));
+        (a, b) = (b, remainder);
+    }
+    out
+}
+
+/// the whole key, if d is small enough (under n^(1/4) / 3 is guaranteed)
+pub fn wiener(public: &RsaPubKey) -> Option<RsaKey> {
+    let (n, e) = (&public.modulus, &public.public_exponent);
+    for (k, d) in convergents(e, n) {
+        if k.is_zero() {
+            continue;
+        }
+        let (phi, remainder) = (e * &d - 1u32).div_rem(&k);
+        if !remainder.is_zero() || phi >= *n {
+            continue;
+        }
+        // p + q, and (p - q)^2 = (p + q)^2 - 4n
+        let sum = n - &phi + 1u32;
+        let square = &sum * &sum;
+        if square < n * 4u32 {
+            continue;
+        }
+        let Some(difference) = exact_root(&(square - n * 4u32), 2) else {
+            continue;
+        };
+        let p = (&sum + &difference) >> 1;
+        let q = (&sum - &difference) >> 1;
+        if &p * &q == *n {
+            return RsaKey::from_parts(p, q, e.clone(), d).ok();
+        }
+    }
+    None
+}
+
+// c^x mod n, for a negative x too
+fn signed_pow(c: &BigUint, x: &BigInt, n: &BigUint) -> Result<BigUint> {
+    let base = if x.is_negative() {
+        invmod(c.clone(), n.clone()).ok_or_else(|| anyhow!("the ciphertext isn't invertible"))?
+    } else {
+        c.clone()
+    };
+    Ok(base.modpow(&x.magnitude().clone(), n))
+}
+
+/// m, from its encryptions under two keys with the same n and coprime e's
+pub fn common_modulus(
+    first: (&RsaPubKey, &BigUint),
+    second: (&RsaPubKey, &BigUint),
+) -> Result<BigUint> {
+    let ((key_1, c_1), (key_2, c_2)) = (first, second);
+    if key_1.modulus != key_2.modulus {
+        return Err(anyhow!("those keys have different moduli"));
+    }
+    let n = &key_1.modulus;
+    let (g, a, b) = egcd(&key_1.public_exponent, &key_2.public_exponent);
+    if !g.is_one() {
+        return Err(anyhow!("the public exponents aren't coprime (gcd {g})"));
+    }
+    Ok(signed_pow(c_1, &a, n)? * signed_pow(c_2, &b, n)? % n)
+}
+
+// levels of products, the leaves first and the product of everything last
+fn product_tree(leaves: &[BigUint]) -> Vec<Vec<BigUint>> {
+    let mut tree = vec![leaves.to_vec()];
+    while tree.last().unwrap().len() > 1 {
+        let next = tree
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| pair.iter().product())
+            .collect();
+        tree.push(next);
+    }
+    tree
+}
+
+/// (p, q) for every key that shares a prime with another one in the list, None for the rest
+pub fn batch_gcd(keys: &[RsaPubKey]) -> Vec<Option<(BigUint, BigUint)>> {
+    let moduli = keys.iter().map(|k| k.modulus.clone()).collect::<Vec<_>>();
+    if moduli.is_empty() {
+        return vec![];
+    }
+    // down the tree, product mod n^2 at each node, so at the leaves it's P mod n_i^2
+    let tree = product_tree(&moduli);
+    let mut remainders = tree.last().unwrap().clone();
+    for level in tree.iter().rev().skip(1) {
+        remainders = level
+            .iter()
+            .enumerate()
+            .map(|(i, node)| &remainders[i / 2] % (node * node))
+            .collect();
+    }
+    moduli
+        .iter()
+        .zip(remainders)
+        .enumerate()
+        .map(|(i, (n, remainder))| {
+            // (P / n) mod n, and its gcd with n
+            let g = (remainder / n).gcd(n);
+            if &g != n {
+                return nontrivial(n, g);
+            }
+            // both primes are shared, maybe with two different keys: one at a time
+            moduli
+                .iter()
+                .enumerate()
+                .filter(|&(j, other)| j != i && other != n)
+                .find_map(|(_, other)| nontrivial(n, n.gcd(other)))
+        })
+        .collect()
+}
+
+/// what actually got encrypted: a * m + b mod n
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Affine {
+    pub a: BigUint,
+    pub b: BigUint,
+}
+
+impl Affine {
+    pub fn apply(&self, m: &BigUint, n: &BigUint) -> BigUint {
+        (&self.a * m + &self.b) % n
+    }
+}
+
+// everything Hastad needs to be true about the keys, and the e they share
+fn broadcast_exponent(ciphertexts: &[BigUint], keys: &[RsaPubKey]) -> Result<u32> {
+    if ciphertexts.len() != keys.len() || keys.is_empty() {
+        return Err(anyhow!(
+            "{} ciphertexts for {} keys",
+            ciphertexts.len(),
+            keys.len()
+        ));
+    }
+    let e = &keys[0].public_exponent;
+    if keys.iter().any(|key| key.public_exponent != *e) {
+        return Err(anyhow!("the keys don't all have the same public exponent"));
+    }
+    u32::try_from(e).map_err(|_| anyhow!("e = {e} is too big for a broadcast attack"))
+}
+
+/// m, from its encryption under several keys with the same small e. e of them is always enough
+pub fn hastad_broadcast(ciphertexts: &[BigUint], keys: &[RsaPubKey]) -> Result<BigUint> {
+    let e = broadcast_exponent(ciphertexts, keys)?;
+    let moduli = keys.iter().map(|k| k.modulus.clone()).collect::<Vec<_>>();
+    let m_to_the_e = crt(ciphertexts, &moduli)?;
+    exact_root(&m_to_the_e, e).ok_or_else(|| {
+        anyhow!(
+            "m^{e} wrapped the product of {} moduli: it needs more ciphertexts",
+            keys.len()
+        )
+    })
+}
+
+/// m, from encryptions of paddings[i].apply(m) under keys[i] with the same small e. when m is as
+/// big as the moduli that's more than e keys, and more than e (e + 1) / 2 to be quick about it
+pub fn hastad_broadcast_with_padding(
+    ciphertexts: &[BigUint],
+    keys: &[RsaPubKey],
+    paddings: &[Affine],
+) -> Result<BigUint> {
+    let e = broadcast_exponent(ciphertexts, keys)?;
+    if paddings.len() != keys.len() {
+        return Err(anyhow!(
+            "{} paddings for {} keys",
+            paddings.len(),
+            keys.len()
+        ));
+    }
+    let moduli = keys.iter().map(|k| k.modulus.clone()).collect::<Vec<_>>();
+    let degree = e as usize;
+
+    // (a x + b)^e - c mod each n, then the CRT on each coefficient
+    let mut coefficients = vec![vec![]; degree + 1];
+    for ((c, n), padding) in ciphertexts.iter().zip(&moduli).zip(paddings) {
+        let mut f = affine_power(&padding.a, &padding.b, e, n);
+        f[0] = (&f[0] + n - c % n) % n;
+        for (slot, coefficient) in coefficients.iter_mut().zip(f) {
+            slot.push(coefficient);
+        }
+    }
+    let g = coefficients
+        .iter()
+        .map(|residues| crt(residues, &moduli))
+        .collect::<Result<Vec<_>>>()?;
+
+    let big_n = moduli.iter().product::<BigUint>();
+    let bound = moduli.iter().min().unwrap();
+    let too_few = || {
+        anyhow!(
+            "no small root with {} keys: it needs more ciphertexts",
+            keys.len()
+        )
+    };
+    for m in small_roots(&g, &big_n, bound, 1.0).map_err(|_| too_few())? {
+        let works = ciphertexts
+            .iter()
+            .zip(keys)
+            .zip(paddings)
+            .all(|((c, key), padding)| {
+                key.encrypt(&padding.apply(&m, &key.modulus)).ok().as_ref() == Some(c)
+            });
+        if works {
+            return Ok(m);
+        }
+    }
+    Err(too_few())
+}
+
+/// m1, from c1 = m1^e and c2 = m2^e under the same key, where m2 = related.apply(m1)
+pub fn franklin_reiter(
+    public: &RsaPubKey,
+    (c1, c2): (&BigUint, &BigUint),
+    related: &Affine,
+) -> Result<BigUint> {
+    let n = &public.modulus;
+    let e = u32::try_from(&public.public_exponent)
+        .map_err(|_| anyhow!("e is far too big for a polynomial gcd"))?;
+    // x^e - c1 and (a x + b)^e - c2
+    let mut first = affine_power(&BigUint::one(), &BigUint::zero(), e, n);
+    first[0] = (n - c1 % n) % n;
+    let mut second = affine_power(&related.a, &related.b, e, n);
+    second[0] = (&second[0] + n - c2 % n) % n;
+
+    match gcd(&first, &second, n)?.as_slice() {
+        [constant, _] => {
+            let m = (n - constant) % n;
+            if public.encrypt(&m)? != *c1 || public.encrypt(&related.apply(&m, n))? != *c2 {
+                return Err(anyhow!("the messages aren't related like that"));
+            }
+            Ok(m)
+        }
+        [_] => Err(anyhow!("the messages aren't related like that")),
+        other => Err(anyhow!(
+            "the gcd has degree {}, not 1: too many messages fit",
+            other.len().saturating_sub(1)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsa::is_probable_prime;
+    use num::bigint::RandBigInt;
+    use rand::seq::SliceRandom;
+
+    fn next_prime(mut n: BigUint) -> BigUint {
+        n |= BigUint::one();
+        while !is_probable_prime(&n, 20) {
+            n += 2u32;
+        }
+        n
+    }
+
+    fn check(n: &BigUint, found: Option<(BigUint, BigUint)>) {
+        let (p, q) = found.expect("should have factored it");
+        assert!(p > q && q > BigUint::one());
+        assert_eq!(&p * &q, *n);
+    }
+
+    #[test]
+    fn test_fermat() {
+        let p = RsaKey::gen_prime(256);
+        let q = next_prime(&p + 1000u32);
+        let n = &p * &q;
+        check(&n, fermat(&n, 10));
+        assert_eq!(fermat(&n, 10).unwrap(), (q, p));
+        // an ordinary key is out of reach
+        let key = RsaKey::new(128);
+        assert_eq!(fermat(&key.modulus, 1000), None);
+        check(
+            &BigUint::from(2u32 * 1009),
+            fermat(&BigUint::from(2u32 * 1009), 1),
+        );
+    }
+
+    #[test]
+    fn test_pollard_p_minus_1() {
+        // p - 1 = 2 * (different primes under 1000)
+        let small_primes = (3..1000u32)
+            .filter(|&p| is_probable_prime(&BigUint::from(p), 20))
+            .collect::<Vec<_>>();
+        let smooth = loop {
+            let mut p_minus_1 = BigUint::from(2u32);
+            for &prime in small_primes.choose_multiple(&mut rand::thread_rng(), 24) {
+                p_minus_1 *= prime;
+            }
+            if is_probable_prime(&(&p_minus_1 + 1u32), 20) {
+                break p_minus_1 + 1u32;
+            }
+        };
+        let q = RsaKey::gen_prime(200);
+        let n = &smooth * &q;
+        let found = pollard_p_minus_1(&n, 1000);
+        check(&n, found.clone());
+        let (p, q) = found.unwrap();
+        assert!(p == smooth || q == smooth);
+        assert_eq!(pollard_p_minus_1(&RsaKey::new(128).modulus, 1000), None);
+    }
+
+    #[test]
+    fn test_pollard_rho() {
+        let small = next_prime(BigUint::from(1u32 << 24) + 12345u32);
+        let n = &small * RsaKey::gen_prime(256);
+        let (_, q) = pollard_rho(&n, 100_000).unwrap();
+        assert_eq!(q, small);
+        check(
+            &BigUint::from(8051u32),
+            pollard_rho(&BigUint::from(8051u32), 100),
+        );
+    }
+
+    #[test]
+    fn test_wiener() {
+        let mut rng = rand::thread_rng();
+        let p = RsaKey::gen_prime(256);
+        let q = RsaKey::gen_prime(256);
+        let phi = (&p - 1u32) * (&q - 1u32);
+        // d well under n^(1/4) / 3, which is about 2^126
+        let (d, e) = loop {
+            let d = rng.gen_biguint(100) | BigUint::one();
+            if let Some(e) = invmod(d.clone(), phi.clone()) {
+                break (d, e);
+            }
+        };
+        let key = RsaKey::from_parts(p, q, e, d.clone()).unwrap();
+        key.validate().unwrap();
+        let found = wiener(&key.get_public_key()).unwrap();
+        assert_eq!(found.private_exponent, d);
+        assert_eq!(found.modulus, key.modulus);
+        found.validate().unwrap();
+
+        assert!(wiener(&RsaKey::new(256).get_public_key()).is_none());
+    }
+
+    #[test]
+    fn test_common_modulus() {
+        let key = RsaKey::new(256);
+        let first = key.get_public_key();
+        // the second key doesn't even have to be a working one
+        let second = RsaPubKey {
+            public_exponent: BigUint::from(17u32),
+            ..first.clone()
+        };
+        let m = BigUint::from(0x68692c206d6f6du64);
+        let c_1 = first.encrypt(&m).unwrap();
+        let c_2 = second.encrypt(&m).unwrap();
+        assert_eq!(common_modulus((&first, &c_1), (&second, &c_2)).unwrap(), m);
+        assert_eq!(common_modulus((&second, &c_2), (&first, &c_1)).unwrap(), m);
+
+        let not_coprime = RsaPubKey {
+            public_exponent: BigUint::from(65537u32 * 3),
+            ..first.clone()
+        };
+        assert!(common_modulus((&first, &c_1), (&not_coprime, &c_2)).is_err());
+        let other = RsaKey::new(256).get_public_key();
+        assert!(common_modulus((&first, &c_1), (&other, &c_2)).is_err());
+    }
+
+    #[test]
+    fn test_batch_gcd() {
+        let primes = (0..7).map(|_| RsaKey::gen_prime(128)).collect::<Vec<_>>();
+        let key = |i: usize, j: usize| RsaPubKey {
+            modulus: &primes[i] * &primes[j],
+            public_exponent: BigUint::from(65537u32),
+        };
+        // 1 shares nothing. 2 shares one prime with 0. 0, 3, 4 and 5 share both of theirs, with
+        // different keys, so the remainder tree gives back n for them
+        let keys = [
+            key(0, 1),
+            key(2, 3),
+            key(1, 4),
+            key(5, 6),
+            key(5, 0),
+            key(6, 0),
+            key(2, 3),
+        ];
+        let found = batch_gcd(&keys[..6]);
+        assert_eq!(found.len(), 6);
+        for (i, found) in found.into_iter().enumerate() {
+            if i == 1 {
+                assert!(found.is_none());
+            } else {
+                check(&keys[i].modulus, found);
+            }
+        }
+        // a repeated key isn't a shared prime we can do anything with
+        let found = batch_gcd(&[keys[1].clone(), keys[6].clone(), keys[2].clone()]);
+        assert_eq!(found, vec![None, None, None]);
+        assert!(batch_gcd(&[]).is_empty());
+    }
+
+    fn broadcast(e: u32, count: usize, m: &BigUint) -> (Vec<BigUint>, Vec<RsaPubKey>) {
+        let keys = (0..count)
+            .map(|_| RsaKey::with_exponent(256, &BigUint::from(e)).get_public_key())
+            .collect::<Vec<_>>();
+        let ciphertexts = keys.iter().map(|key| key.encrypt(m).unwrap()).collect();
+        (ciphertexts, keys)
+    }
+
+    #[test]
+    fn test_hastad_broadcast() {
+        let m = BigUint::from_bytes_be(b"i love doggsss, and all the other animals too");
+        for e in [3, 5] {
+            let (ciphertexts, keys) = broadcast(e, e as usize, &m);
+            assert_eq!(hastad_broadcast(&ciphertexts, &keys).unwrap(), m);
+        }
+        // a small enough message doesn't need all of them
+        let small = BigUint::from(0xdecafbadu64);
+        let (ciphertexts, keys) = broadcast(3, 1, &small);
+        assert_eq!(hastad_broadcast(&ciphertexts, &keys).unwrap(), small);
+
+        let (ciphertexts, keys) = broadcast(3, 2, &BigUint::from_bytes_be(&[0xff; 63]));
+        let error = hastad_broadcast(&ciphertexts, &keys).unwrap_err();
+        assert!(error.to_string().contains("needs more ciphertexts"));
+        // the same key twice isn't any help
+        let twice = [keys[0].clone(), keys[0].clone()];
+        let error = hastad_broadcast(&[ciphertexts[0].clone(), ciphertexts[0].clone()], &twice)
+            .unwrap_err();
+        assert!(error.to_string().contains("moduli 0 and 1 aren't coprime"));
+        let mixed = [keys[0].clone(), RsaKey::new(256).get_public_key()];
+        assert!(hastad_broadcast(&ciphertexts, &mixed).is_err());
+        assert!(hastad_broadcast(&ciphertexts[..1], &keys).is_err());
+    }
+
+    #[test]
+    fn test_hastad_with_padding() {
+        // each recipient's copy has their user id stuck on the end, and is in a different
+        // message format (the multiplier)
+        let m = BigUint::from_bytes_be(b"meet me by the old oak tree");
+        let keys = (0..7)
+            .map(|_| RsaKey::with_exponent(128, &BigUint::from(3u32)).get_public_key())
+            .collect::<Vec<_>>();
+        let paddings = (0..7u32)
+            .map(|i| Affine {
+                a: BigUint::from(1u32 + i % 3) << 16,
+                b: BigUint::from(1000 + i),
+            })
+            .collect::<Vec<_>>();
+        let ciphertexts = keys
+            .iter()
+            .zip(&paddings)
+            .map(|(key, padding)| key.encrypt(&padding.apply(&m, &key.modulus)).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(hastad_broadcast(&ciphertexts, &keys).is_err());
+        assert_eq!(
+            hastad_broadcast_with_padding(&ciphertexts, &keys, &paddings).unwrap(),
+            m
+        );
+        // no padding at all is a padding too
+        let plain = keys
+            .iter()
+            .map(|key| key.encrypt(&m).unwrap())
+            .collect::<Vec<_>>();
+        let identity = vec![
+            Affine {
+                a: BigUint::one(),
+                b: BigUint::zero()
+            };
+            7
+        ];
+        assert_eq!(
+            hastad_broadcast_with_padding(&plain, &keys, &identity).unwrap(),
+            m
+        );
+        assert!(hastad_broadcast_with_padding(&ciphertexts, &keys, &paddings[1..]).is_err());
+    }
+
+    #[test]
+    fn test_franklin_reiter() {
+        // the same message sent twice, with a counter in the last byte that went up by one
+        let m1 = BigUint::from_bytes_be(b"transfer $100 to mallory, #\x07");
+        let related = Affine {
+            a: BigUint::one(),
+            b: BigUint::one(),
+        };
+        for e in [3u32, 17] {
+            let key = RsaKey::with_exponent(256, &BigUint::from(e)).get_public_key();
+            let m2 = related.apply(&m1, &key.modulus);
+            let c1 = key.encrypt(&m1).unwrap();
+            let c2 = key.encrypt(&m2).unwrap();
+            assert_eq!(franklin_reiter(&key, (&c1, &c2), &related).unwrap(), m1);
+        }
+
+        // a relation with a multiplier in it too
+        let key = RsaKey::with_exponent(256, &BigUint::from(3u32)).get_public_key();
+        let related = Affine {
+            a: BigUint::from(256u32),
+            b: BigUint::from(b'!'),
+        };
+        let c1 = key.encrypt(&m1).unwrap();
+        let c2 = key.encrypt(&related.apply(&m1, &key.modulus)).unwrap();
+        assert_eq!(franklin_reiter(&key, (&c1, &c2), &related).unwrap(), m1);
+        // the wrong relation
+        let wrong = Affine {
+            a: BigUint::from(256u32),
+            b: BigUint::from(b'?'),
+        };
+        assert!(franklin_reiter(&key, (&c1, &c2), &wrong).is_err());
+    }
+}
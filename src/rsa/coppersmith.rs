@@ -0,0 +1,329 @@
+// Coppersmith's small roots, in Howgrave-Graham's version: given a monic f of degree d and a
+// bound X, find every x0 <= X with f(x0) = 0 mod b, for some unknown b dividing N with
+// b >= N^beta. beta = 1 is the usual "mod N"; beta = 1/2 with b = p is how you factor N.
+//
+// Pick m and t, and take the polynomials
+//
+//   x^j N^(m - i) f(x)^i    for i < m, j < d
+//   x^i f(x)^m              for i < t
+//
+// All of them are 0 mod b^m at x0. Their degrees are 0, 1, ..., n - 1 (n = d m + t), so with the
+// coefficients of each g(x X) in a row the basis is triangular, and its determinant is
+// N^(d m (m + 1) / 2) X^(n (n - 1) / 2). LLL finds an h in there with |h(x X)| about
+// det^(1/n), and if that's under b^m / sqrt(n) then |h(x0)| < b^m, so h(x0) = 0 over the
+// integers (see lattice.rs). That works up to X = N^(beta^2 / d), a little less in practice,
+// with m and t going up as X gets closer. parameters() finds the smallest lattice that does it.
+//
+// Two uses:
+//  - stereotyped messages: m = prefix * 2^k + x with only x unknown, and x < N^(1/e). So with
+//    e = 3 a third of the message can be secret and it still comes out.
+//  - factoring with the top half of p: f(x) = p_top * 2^k + x has x0 = the rest of p as a root
+//    mod p. Exactly half is right at N^(1/4), so guess a few more bits until it isn't.
+
+use super::lattice::{evaluate, integer_roots, lll};
+use super::{i2osp, invmod, RsaPubKey};
+use anyhow::{anyhow, Result};
+use num::{BigInt, BigUint, Integer, One, Zero};
+
+// LLL on anything much bigger takes minutes
+const MAX_DIMENSION: usize = 40;
+// factor_with_high_bits would rather guess bits than use a lattice bigger than this
+const FACTORING_DIMENSION: usize = 12;
+// and tries up to 2^this guesses
+const MAX_GUESSED_BITS: usize = 12;
+
+fn multiply(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let mut product = vec![BigInt::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+// is a lattice from m and t small enough for a root up to 2^bound_bits?
+fn fits(degree: usize, m: usize, t: usize, modulus_bits: f64, bound_bits: f64, beta: f64) -> bool {
+    let n = degree * m + t;
+    let det =
+        (degree * m * (m + 1) / 2) as f64 * modulus_bits + (n * (n - 1) / 2) as f64 * bound_bits;
+    det / n as f64 + (n as f64).log2() / 2.0 < m as f64 * beta * modulus_bits
+}
+
+/// (m, t) for the smallest lattice that should find roots up to 2^bound_bits of a degree d
+/// polynomial mod b >= N^beta, or None if it'd need more than MAX_DIMENSION rows
+pub fn parameters(
+    degree: usize,
+    modulus_bits: u64,
+    bound_bits: u64,
+    beta: f64,
+) -> Option<(usize, usize)> {
+    (2..=MAX_DIMENSION).find_map(|n| {
+        (1..=n / degree)
+            .map(|m| (m, n - degree * m))
+            .find(|&(m, t)| fits(degree, m, t, modulus_bits as f64, bound_bits as f64, beta))
+    })
+}
+
+// small_roots with m and t already picked
+fn small_roots_with(
+    poly: &[BigUint],
+    modulus: &BigUint,
+    bound: &BigUint,
+    beta: f64,
+    (m, t): (usize, usize),
+) -> Result<Vec<BigUint>> {
+    let degree = poly.len() - 1;
+    let n = BigInt::from(modulus.clone());
+    let monic = invmod(poly[degree].clone(), modulus.clone())
+        .ok_or_else(|| anyhow!("the leading coefficient isn't invertible mod n"))?;
+    let f = poly
+        .iter()
+        .map(|coefficient| BigInt::from(coefficient * &monic % modulus))
+        .collect::<Vec<_>>();
+
+    let enough_bits = beta * (modulus.bits() - 1) as f64;
+    let is_root = |x: &BigInt| evaluate(&f, x).mod_floor(&n).gcd(&n).bits() as f64 >= enough_bits;
+    // everything but the constant column would be zero, which LLL won't take
+    if bound.is_zero() {
+        return Ok(if is_root(&BigInt::zero()) {
+            vec![BigUint::zero()]
+        } else {
+            vec![]
+        });
+    }
+
+    let dimension = degree * m + t;
+    let bound_int = BigInt::from(bound.clone());
+    let scale = (0..dimension)
+        .map(|power| bound_int.pow(power as u32))
+        .collect::<Vec<_>>();
+    let mut f_powers = vec![vec![BigInt::one()]];
+    for i in 0..m {
+        f_powers.push(multiply(&f_powers[i], &f));
+    }
+    let row = |poly: &[BigInt], shift: usize, multiplier: &BigInt| {
+        let mut row = vec![BigInt::zero(); dimension];
+        for (power, coefficient) in poly.iter().enumerate() {
+            row[power + shift] = coefficient * multiplier * &scale[power + shift];
+        }
+        row
+    };
+    let mut basis = vec![];
+    for (i, f_power) in f_powers[..m].iter().enumerate() {
+        let multiplier = n.pow((m - i) as u32);
+        for j in 0..degree {
+            basis.push(row(f_power, j, &multiplier));
+        }
+    }
+    for i in 0..t {
+        basis.push(row(&f_powers[m], i, &BigInt::one()));
+    }
+    lll(&mut basis)?;
+
+    let mut roots = vec![];
+    for row in &basis {
+        let h = row
+            .iter()
+            .zip(&scale)
+            .map(|(coefficient, scale)| coefficient / scale)
+            .collect::<Vec<_>>();
+        for root in integer_roots(&h, &BigInt::zero(), &bound_int) {
+            if is_root(&root) {
+                roots.push(root.magnitude().clone());
+            }
+        }
+    }
+    roots.sort();
+    roots.dedup();
+    Ok(roots)
+}
+
+/// every x0 <= bound with poly(x0) = 0 mod some b >= modulus^beta dividing modulus, with
+/// coefficients lowest power first. beta = 1.0 for roots mod modulus itself. near the limit it
+/// can miss one, and the roots that come back are worth checking
+pub fn small_roots(
+    poly: &[BigUint],
+    modulus: &BigUint,
+    bound: &BigUint,
+    beta: f64,
+) -> Result<Vec<BigUint>> {
+    let degree = poly
+        .iter()
+        .rposition(|c| !(c % modulus).is_zero())
+        .filter(|&degree| degree > 0)
+        .ok_or_else(|| anyhow!("the polynomial has to have a degree mod n"))?;
+    let found = parameters(degree, modulus.bits(), bound.bits(), beta).ok_or_else(|| {
+        anyhow!(
+            "a {}-bit bound is too big for degree {degree} mod a {}-bit n",
+            bound.bits(),
+            modulus.bits()
+        )
+    })?;
+    small_roots_with(&poly[..=degree], modulus, bound, beta, found)
+}
+
+/// the coefficients of (a x + b)^e mod n, lowest power first
+pub fn affine_power(a: &BigUint, b: &BigUint, e: u32, n: &BigUint) -> Vec<BigUint> {
+    let mut binomial = BigUint::one();
+    (0..=e)
+        .map(|power| {
+            let coefficient = &binomial
+                * a.modpow(&BigUint::from(power), n)
+                * b.modpow(&BigUint::from(e - power), n)
+                % n;
+            binomial = &binomial * (e - power) / (power + 1);
+            coefficient
+        })
+        .collect()
+}
+
+/// the last unknown_len bytes of a message that's prefix followed by them, from its encryption.
+/// needs the unknown part to be under n^(1/e), so a small e
+pub fn known_prefix(
+    public: &RsaPubKey,
+    ciphertext: &BigUint,
+    prefix: &[u8],
+    unknown_len: usize,
+) -> Result<Vec<u8>> {
+    let n = &public.modulus;
+    let e =
+        u32::try_from(&public.public_exponent).map_err(|_| anyhow!("e is far too big for this"))?;
+    let shift = 8 * unknown_len;
+    let known = BigUint::from_bytes_be(prefix) << shift;
+    let mut f = affine_power(&BigUint::one(), &known, e, n);
+    f[0] = (&f[0] + n - ciphertext % n) % n;
+    let bound = (BigUint::one() << shift) - 1u32;
+
+    for x in small_roots(&f, n, &bound, 1.0)? {
+        if public.encrypt(&(&known + &x))? == *ciphertext {
+            return i2osp(&x, unknown_len);
+        }
+    }
+    Err(anyhow!("no message starts with that prefix"))
+}
+
+/// factor n = p q, knowing all but the bottom unknown_bits bits of p (p_high is p >> unknown_bits).
+/// half of p's bits is enough, with some guessing
+pub fn factor_with_high_bits(
+    n: &BigUint,
+    p_high: &BigUint,
+    unknown_bits: usize,
+) -> Result<(BigUint, BigUint)> {
+    // p >= 2^(bits of p - 1) >= n^beta
+    let p_bits = p_high.bits() + unknown_bits as u64;
+    if p_bits == 0 {
+        return Err(anyhow!("p can't be 0"));
+    }
+    let beta = (p_bits - 1) as f64 / n.bits() as f64;
+    let (guessed, found) = (0..=MAX_GUESSED_BITS.min(unknown_bits))
+        .find_map(|guessed| {
+            let bits = (unknown_bits - guessed) as u64;
+            parameters(1, n.bits(), bits, beta)
+                .filter(|&(m, t)| m + t <= FACTORING_DIMENSION)
+                .map(|found| (guessed, found))
+        })
+        .ok_or_else(|| anyhow!("{unknown_bits} unknown bits of p is too many"))?;
+
+    let unknown_bits = unknown_bits - guessed;
+    let bound = (BigUint::one() << unknown_bits) - 1u32;
+    for guess in 0..1u32 << guessed {
+        let known = ((p_high << guessed) + guess) << unknown_bits;
+        let f = [&known % n, BigUint::one()];
+        for x in small_roots_with(&f, n, &bound, beta, found)? {
+            let p = &known + x;
+            if p > BigUint::one() && &p < n && (n % &p).is_zero() {
+                let q = n / &p;
+                return Ok(if p > q { (p, q) } else { (q, p) });
+            }
+        }
+    }
+    Err(anyhow!("p doesn't start with those bits"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsa::RsaKey;
+    use num::bigint::RandBigInt;
+
+    #[test]
+    fn test_affine_power() {
+        // (2x + 3)^3 = 8x^3 + 36x^2 + 54x + 27
+        let n = BigUint::from(1000u32);
+        let coefficients = affine_power(&BigUint::from(2u32), &BigUint::from(3u32), 3, &n);
+        let expected = [27u32, 54, 36, 8].map(BigUint::from);
+        assert_eq!(coefficients, expected);
+        let coefficients = affine_power(&BigUint::from(2u32), &BigUint::from(3u32), 3, &n.sqrt());
+        let expected = [27u32, 54, 36, 8].map(|c| BigUint::from(c % 31));
+        assert_eq!(coefficients, expected);
+    }
+
+    #[test]
+    fn test_small_roots() {
+        let key = RsaKey::with_exponent(256, &BigUint::from(3u32));
+        let n = &key.get_public_key().modulus;
+        let x0 = rand::thread_rng().gen_biguint(100);
+        // (x - x0)(x + 12345) mod n
+        let poly = [
+            (n - &x0) * 12345u32 % n,
+            (BigUint::from(12345u32) + n - &x0) % n,
+            BigUint::one(),
+        ];
+        let roots = small_roots(&poly, n, &(BigUint::one() << 100), 1.0).unwrap();
+        assert!(roots.contains(&x0));
+
+        // a root mod p, not n
+        let p = key.p.clone();
+        let poly = [(&p - &x0) % n, BigUint::one()];
+        let roots = small_roots(&poly, n, &(BigUint::one() << 100), 0.49).unwrap();
+        assert_eq!(roots, [x0]);
+
+        assert!(small_roots(&poly, n, &(BigUint::one() << 200), 0.49).is_err());
+        assert!(small_roots(&[BigUint::one()], n, &BigUint::one(), 1.0).is_err());
+    }
+
+    #[test]
+    fn test_known_prefix() {
+        let key = RsaKey::with_exponent(256, &BigUint::from(3u32));
+        let public = key.get_public_key();
+        let prefix = b"the password for today is ";
+        let password = b"swordfish-42";
+        let message = BigUint::from_bytes_be(&[&prefix[..], password].concat());
+        let ciphertext = public.encrypt(&message).unwrap();
+
+        let recovered = known_prefix(&public, &ciphertext, prefix, password.len()).unwrap();
+        assert_eq!(recovered, password);
+        // nothing unknown, or an unknown part that's all zeros
+        let whole = [&prefix[..], password].concat();
+        assert!(known_prefix(&public, &ciphertext, &whole, 0)
+            .unwrap()
+            .is_empty());
+        let zeros = public
+            .encrypt(&BigUint::from_bytes_be(&[&prefix[..], &[0; 4]].concat()))
+            .unwrap();
+        assert_eq!(known_prefix(&public, &zeros, prefix, 4).unwrap(), [0; 4]);
+        assert!(known_prefix(&public, &ciphertext, b"the password for today was ", 12).is_err());
+        // more than a third of n
+        assert!(known_prefix(&public, &ciphertext, b"", 40).is_err());
+    }
+
+    #[test]
+    fn test_factor_with_high_bits() {
+        // exactly the top half of p, which needs some guessing, and then a bit more than half of
+        // the other prime with a bigger n
+        for (bits, unknown_bits) in [(48, 24), (256, 100)] {
+            let key = RsaKey::with_exponent(bits, &BigUint::from(3u32));
+            let n = &key.get_public_key().modulus;
+            let p = if bits == 48 { &key.p } else { &key.q };
+            let (found_p, found_q) =
+                factor_with_high_bits(n, &(p >> unknown_bits), unknown_bits).unwrap();
+            assert_eq!(&found_p * &found_q, *n);
+            assert!(found_p == *p || found_q == *p);
+        }
+        let key = RsaKey::with_exponent(256, &BigUint::from(3u32));
+        let n = &key.get_public_key().modulus;
+        assert!(factor_with_high_bits(n, &(&key.p >> 200), 200).is_err());
+        assert!(factor_with_high_bits(n, &BigUint::zero(), 0).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+// Just enough ASN.1 DER to read and write RSA keys: INTEGER (non-negative only), NULL, OBJECT
+// IDENTIFIER, OCTET STRING, BIT STRING (whole bytes only) and SEQUENCE.
+//
+// Everything is tag, length, contents. Short lengths (under 128) are one byte; longer ones are
+// 0x80 + how many bytes of big endian length follow. DER says there's exactly one way to write
+// anything, so the decoder is strict about it: shortest lengths, no extra leading zeros on
+// integers. That's what lets us read a key and write back the same bytes.
+
+use anyhow::{anyhow, Result};
+use num::BigUint;
+
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Asn1 {
+    Integer(BigUint),
+    Null,
+    ObjectIdentifier(Oid),
+    OctetString(Vec<u8>),
+    BitString(Vec<u8>),
+    Sequence(Vec<Asn1>),
+}
+
+/// the arcs of an OBJECT IDENTIFIER. DER packs the first two into one number, so there have to be
+/// at least two, the first is 0, 1 or 2, and under 2 the second is under 40
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Oid(Vec<u64>);
+
+impl Oid {
+    pub fn new(arcs: &[u64]) -> Result<Oid> {
+        match arcs {
+            [0 | 1, second, ..] if *second < 40 => {}
+            [2, second, ..] if *second <= u64::MAX - 80 => {}
+            _ => return Err(anyhow!("{arcs:?} isn't an object identifier")),
+        }
+        Ok(Oid(arcs.to_vec()))
+    }
+
+    pub fn arcs(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+fn encode_length(length: usize, out: &mut Vec<u8>) {
+    if length < 0x80 {
+        out.push(length as u8);
+        return;
+    }
+    let bytes = length.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    out.push(0x80 | (bytes.len() - skip) as u8);
+    out.extend_from_slice(&bytes[skip..]);
+}
+
+// base 128, high bit set on all but the last byte
+fn encode_base128(mut value: u64, out: &mut Vec<u8>) {
+    let mut digits = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value > 0 {
+        digits.push(0x80 | (value & 0x7f) as u8);
+        value >>= 7;
+    }
+    out.extend(digits.iter().rev());
+}
+
+fn encode_into(value: &Asn1, out: &mut Vec<u8>) {
+    let (tag, contents) = match value {
+        Asn1::Integer(n) => {
+            let mut bytes = n.to_bytes_be();
+            // a leading 1 bit would make it negative
+            if bytes[0] & 0x80 != 0 {
+                bytes.insert(0, 0);
+            }
+            (INTEGER, bytes)
+        }
+        Asn1::Null => (NULL, vec![]),
+        Asn1::ObjectIdentifier(Oid(arcs)) => {
+            let mut bytes = vec![];
+            // the first two arcs share a number
+            encode_base128(arcs[0] * 40 + arcs[1], &mut bytes);
+            for &arc in &arcs[2..] {
+                encode_base128(arc, &mut bytes);
+            }
+            (OBJECT_IDENTIFIER, bytes)
+        }
+        Asn1::OctetString(bytes) => (OCTET_STRING, bytes.clone()),
+        Asn1::BitString(bytes) => {
+            // first byte: how many unused bits at the end
+            let mut contents = vec![0];
+            contents.extend_from_slice(bytes);
+            (BIT_STRING, contents)
+        }
+        Asn1::Sequence(items) => {
+            let mut contents = vec![];
+            for item in items {
+                encode_into(item, &mut contents);
+            }
+            (SEQUENCE, contents)
+        }
+    };
+    out.push(tag);
+    encode_length(contents.len(), out);
+    out.extend(contents);
+}
+
+pub fn encode(value: &Asn1) -> Vec<u8> {
+    let mut out = vec![];
+    encode_into(value, &mut out);
+    out
+}
+
+// (tag, contents, the rest)
+fn split_tlv(bytes: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = bytes
+        .split_first()
+        .ok_or_else(|| anyhow!("ran out of DER"))?;
+    let (&first, rest) = rest
+        .split_first()
+        .ok_or_else(|| anyhow!("ran out of DER in a length"))?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > std::mem::size_of::<usize>() || count > rest.len() {
+            return Err(anyhow!("bad DER length"));
+        }
+        let (length_bytes, rest) = rest.split_at(count);
+        let length = length_bytes
+            .iter()
+            .fold(0usize, |length, &b| (length << 8) | b as usize);
+        if length_bytes[0] == 0 || length < 0x80 {
+            return Err(anyhow!("DER length isn't as short as it could be"));
+        }
+        (length, rest)
+    };
+    if length > rest.len() {
+        return Err(anyhow!(
+            "DER says {length} bytes, but there are {}",
+            rest.len()
+        ));
+    }
+    let (contents, rest) = rest.split_at(length);
+    Ok((tag, contents, rest))
+}
+
+fn decode_base128(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    loop {
+        let (&b, rest) = bytes
+            .split_first()
+            .ok_or_else(|| anyhow!("object identifier stops in the middle of a number"))?;
+        *bytes = rest;
+        if value == 0 && b == 0x80 {
+            return Err(anyhow!("object identifier number has a leading zero"));
+        }
+        if value >> 57 != 0 {
+            return Err(anyhow!("object identifier number is too big"));
+        }
+        value = (value << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+// one value off the front, and the rest
+fn decode_one(bytes: &[u8]) -> Result<(Asn1, &[u8])> {
+    let (tag, contents, rest) = split_tlv(bytes)?;
+    let value = match tag {
+        INTEGER => {
+            match contents {
+                [] => return Err(anyhow!("empty INTEGER")),
+                [first, ..] if first & 0x80 != 0 => return Err(anyhow!("negative INTEGER")),
+                [0, second, ..] if second & 0x80 == 0 => {
+                    return Err(anyhow!("INTEGER has a leading zero it doesn't need"))
+                }
+                _ => {}
+            }
+            Asn1::Integer(BigUint::from_bytes_be(contents))
+        }
+        NULL if contents.is_empty() => Asn1::Null,
+        NULL => return Err(anyhow!("NULL with contents")),
+        OBJECT_IDENTIFIER => {
+            let mut remaining = contents;
+            let first = decode_base128(&mut remaining)?;
+            let mut arcs = match first {
+                0..=39 => vec![0, first],
+                40..=79 => vec![1, first - 40],
+                _ => vec![2, first - 80],
+            };
+            while !remaining.is_empty() {
+                arcs.push(decode_base128(&mut remaining)?);
+            }
+            Asn1::ObjectIdentifier(Oid(arcs))
+        }
+        OCTET_STRING => Asn1::OctetString(contents.to_vec()),
+        BIT_STRING => match contents {
+            [0, bytes @ ..] => Asn1::BitString(bytes.to_vec()),
+            _ => return Err(anyhow!("only whole byte BIT STRINGs, please")),
+        },
+        SEQUENCE => {
+            let mut items = vec![];
+            let mut remaining = contents;
+            while !remaining.is_empty() {
+                let (item, rest) = decode_one(remaining)?;
+                items.push(item);
+                remaining = rest;
+            }
+            Asn1::Sequence(items)
+        }
+        _ => return Err(anyhow!("don't know DER tag {tag:#04x}")),
+    };
+    Ok((value, rest))
+}
+
+/// exactly one value, with nothing after it
+pub fn decode(bytes: &[u8]) -> Result<Asn1> {
+    let (value, rest) = decode_one(bytes)?;
+    if !rest.is_empty() {
+        return Err(anyhow!("{} bytes left over after the DER", rest.len()));
+    }
+    Ok(value)
+}
+
+impl Asn1 {
+    pub fn integer(n: u32) -> Asn1 {
+        Asn1::Integer(BigUint::from(n))
+    }
+
+    pub fn as_integer(&self) -> Result<&BigUint> {
+        match self {
+            Asn1::Integer(n) => Ok(n),
+            other => Err(anyhow!("wanted an INTEGER, got {other:?}")),
+        }
+    }
+
+    pub fn as_sequence(&self) -> Result<&[Asn1]> {
+        match self {
+            Asn1::Sequence(items) => Ok(items),
+            other => Err(anyhow!("wanted a SEQUENCE, got {other:?}")),
+        }
+    }
+
+    pub fn as_octet_string(&self) -> Result<&[u8]> {
+        match self {
+            Asn1::OctetString(bytes) => Ok(bytes),
+            other => Err(anyhow!("wanted an OCTET STRING, got {other:?}")),
+        }
+    }
+
+    pub fn as_bit_string(&self) -> Result<&[u8]> {
+        match self {
+            Asn1::BitString(bytes) => Ok(bytes),
+            other => Err(anyhow!("wanted a BIT STRING, got {other:?}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodings() {
+        assert_eq!(encode(&Asn1::integer(0)), [0x02, 0x01, 0x00]);
+        assert_eq!(encode(&Asn1::integer(127)), [0x02, 0x01, 0x7f]);
+        assert_eq!(encode(&Asn1::integer(128)), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(
+            encode(&Asn1::integer(65537)),
+            [0x02, 0x03, 0x01, 0x00, 0x01]
+        );
+        assert_eq!(encode(&Asn1::Null), [0x05, 0x00]);
+        // rsaEncryption
+        let oid = Asn1::ObjectIdentifier(Oid::new(&[1, 2, 840, 113549, 1, 1, 1]).unwrap());
+        assert_eq!(
+            encode(&oid),
+            [0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01]
+        );
+        let long = Asn1::OctetString(vec![0xab; 300]);
+        assert_eq!(encode(&long)[..4], [0x04, 0x82, 0x01, 0x2c]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = Asn1::Sequence(vec![
+            Asn1::integer(0),
+            Asn1::Sequence(vec![
+                Asn1::ObjectIdentifier(Oid::new(&[2, 16, 840, 1, 101, 3, 4, 2, 1]).unwrap()),
+                Asn1::Null,
+            ]),
+            Asn1::Integer(BigUint::from_bytes_be(&[0xff; 129])),
+            Asn1::OctetString(vec![1, 2, 3]),
+            Asn1::BitString(vec![0x80; 200]),
+        ]);
+        assert_eq!(decode(&encode(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn test_oid() {
+        assert_eq!(Oid::new(&[2, 999]).unwrap().arcs(), [2, 999]);
+        for bad in [
+            &[][..],
+            &[1],
+            &[3, 1],
+            &[1, 40],
+            &[0, 40, 1],
+            &[2, u64::MAX],
+        ] {
+            assert!(Oid::new(bad).is_err(), "{bad:?}");
+        }
+        // 2.999 packs into 80 + 999 = 1079, which takes two bytes
+        let oid = Asn1::ObjectIdentifier(Oid::new(&[2, 999, 3]).unwrap());
+        assert_eq!(encode(&oid), [0x06, 0x03, 0x88, 0x37, 0x03]);
+        assert_eq!(decode(&encode(&oid)).unwrap(), oid);
+    }
+
+    #[test]
+    fn test_strict() {
+        // extra leading zero, negative, long form length for a short length, trailing junk
+        for bad in [
+            &[0x02, 0x02, 0x00, 0x01][..],
+            &[0x02, 0x01, 0x80],
+            &[0x04, 0x81, 0x01, 0x00],
+            &[0x05, 0x00, 0x00],
+            &[0x30, 0x03, 0x02, 0x01],
+            &[0x03, 0x02, 0x01, 0xfe],
+            &[0x01, 0x01, 0xff],
+            &[],
+        ] {
+            assert!(decode(bad).is_err(), "{bad:02x?}");
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// Lattices, for the attacks that turn "x is a small root of this polynomial mod N" into "x is a
+// root of this polynomial over the integers", which is easy.
+//
+// The trick (Hastad, then Coppersmith and Howgrave-Graham): write down some polynomials that all
+// vanish mod N at the small root x0, and put their coefficients, scaled by powers of a bound X on
+// x0, in the rows of a basis. Any integer combination of them still vanishes mod N at x0. LLL
+// finds a short one, h, and if h's coefficients are small enough then |h(x0)| < N, so h(x0) isn't
+// just 0 mod N, it's 0. Then find the integer roots of h.
+//
+// LLL here is the all-integer version (Cohen, A Course in Computational Algebraic Number Theory,
+// algorithm 2.6.7): instead of the rational Gram-Schmidt coefficients mu it keeps d_i, the Gram
+// determinants of the first i vectors, and lambda = d * mu, which are integers. No fractions
+// means no fractions to blow up.
+
+use anyhow::{anyhow, Result};
+use num::{BigInt, Integer, One, Signed, Zero};
+
+fn dot(a: &[BigInt], b: &[BigInt]) -> BigInt {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+struct Reducer<'a> {
+    basis: &'a mut [Vec<BigInt>],
+    // d[i] is the Gram determinant of the first i vectors, so d[0] = 1
+    d: Vec<BigInt>,
+    lambda: Vec<Vec<BigInt>>,
+}
+
+impl Reducer<'_> {
+    // make |mu[k][l]| <= 1/2 by taking a multiple of vector l off vector k
+    fn reduce(&mut self, k: usize, l: usize) {
+        let d = &self.d[l + 1];
+        if (&self.lambda[k][l] * 2u32).abs() <= *d {
+            return;
+        }
+        // the nearest integer to lambda / d
+        let q = (&self.lambda[k][l] * 2u32 + d).div_floor(&(d * 2u32));
+        let (before, after) = self.basis.split_at_mut(k);
+        for (x, y) in after[0].iter_mut().zip(&before[l]) {
+            *x -= &q * y;
+        }
+        self.lambda[k][l] -= &q * d;
+        for i in 0..l {
+            let change = &q * &self.lambda[l][i];
+            self.lambda[k][i] -= change;
+        }
+    }
+
+    // swap vectors k - 1 and k, and fix up everything that depended on the order
+    fn swap(&mut self, k: usize, k_max: usize) {
+        self.basis.swap(k, k - 1);
+        for j in 0..k - 1 {
+            let (before, after) = self.lambda.split_at_mut(k);
+            std::mem::swap(&mut before[k - 1][j], &mut after[0][j]);
+        }
+        let lambda = self.lambda[k][k - 1].clone();
+        let b = (&self.d[k - 1] * &self.d[k + 1] + &lambda * &lambda) / &self.d[k];
+        for i in k + 1..=k_max {
+            let t = self.lambda[i][k].clone();
+            self.lambda[i][k] =
+                (&self.d[k + 1] * &self.lambda[i][k - 1] - &lambda * &t) / &self.d[k];
+            self.lambda[i][k - 1] = (&b * &t + &lambda * &self.lambda[i][k]) / &self.d[k + 1];
+        }
+        self.d[k] = b;
+    }
+}
+
+/// LLL with delta = 3/4, in place: afterwards the first row is within 2^((rows - 1) / 2) of the
+/// shortest vector in the lattice. the rows have to be linearly independent
+pub fn lll(basis: &mut [Vec<BigInt>]) -> Result<()> {
+    let n = basis.len();
+    if n == 0 {
+        return Ok(());
+    }
+    let mut reducer = Reducer {
+        d: vec![BigInt::zero(); n + 1],
+        lambda: vec![vec![BigInt::zero(); n]; n],
+        basis,
+    };
+    reducer.d[0] = BigInt::one();
+    reducer.d[1] = dot(&reducer.basis[0], &reducer.basis[0]);
+    if reducer.d[1].is_zero() {
+        return Err(anyhow!("the basis has a zero vector in it"));
+    }
+    let mut k = 1;
+    let mut k_max = 0;
+    while k < n {
+        // Gram-Schmidt for a vector we haven't seen yet
+        if k > k_max {
+            k_max = k;
+            for j in 0..=k {
+                let mut u = dot(&reducer.basis[k], &reducer.basis[j]);
+                for i in 0..j {
+                    u = (&reducer.d[i + 1] * &u - &reducer.lambda[k][i] * &reducer.lambda[j][i])
+                        / &reducer.d[i];
+                }
+                if j < k {
+                    reducer.lambda[k][j] = u;
+                } else if u.is_zero() {
+                    return Err(anyhow!("the basis isn't linearly independent"));
+                } else {
+                    reducer.d[k + 1] = u;
+                }
+            }
+        }
+        reducer.reduce(k, k - 1);
+        // Lovasz's condition, times 4 * d[k]^2 to keep it in integers
+        let lambda = &reducer.lambda[k][k - 1];
+        if &reducer.d[k + 1] * &reducer.d[k - 1] * 4u32
+            < &reducer.d[k] * &reducer.d[k] * 3u32 - lambda * lambda * 4u32
+        {
+            reducer.swap(k, k_max);
+            k = (k - 1).max(1);
+        } else {
+            for l in (0..k - 1).rev() {
+                reducer.reduce(k, l);
+            }
+            k += 1;
+        }
+    }
+    Ok(())
+}
+
+/// a polynomial with coefficients lowest power first, at x
+pub fn evaluate(poly: &[BigInt], x: &BigInt) -> BigInt {
+    poly.iter()
+        .rev()
+        .fold(BigInt::zero(), |total, coefficient| total * x + coefficient)
+}
+
+fn derivative(poly: &[BigInt]) -> Vec<BigInt> {
+    poly.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(power, coefficient)| coefficient * power)
+        .collect()
+}
+
+fn sign(n: &BigInt) -> i8 {
+    if n.is_zero() {
+        0
+    } else if n.is_negative() {
+        -1
+    } else {
+        1
+    }
+}
+
+// every t in [lo, hi] with poly(t) = 0, or where the sign changes between t and t + 1. poly is
+// monotone between the places its derivative does that, so each piece in between is a binary
+// search
+fn crossings(poly: &[BigInt], lo: &BigInt, hi: &BigInt) -> Vec<BigInt> {
+    let degree = match poly.iter().rposition(|c| !c.is_zero()) {
+        Some(degree) if degree > 0 => degree,
+        _ => return vec![],
+    };
+    let poly = &poly[..=degree];
+    let mut points = vec![lo.clone(), hi.clone()];
+    for t in crossings(&derivative(poly), lo, hi) {
+        points.push(&t + 1);
+        points.push(t);
+    }
+    points.retain(|t| t >= lo && t <= hi);
+    points.sort();
+    points.dedup();
+
+    let mut found = vec![];
+    for pair in points.windows(2) {
+        let (mut below, mut above) = (pair[0].clone(), pair[1].clone());
+        let start = sign(&evaluate(poly, &below));
+        if start == 0 {
+            found.push(below.clone());
+        }
+        if start == 0 || start == sign(&evaluate(poly, &above)) {
+            continue;
+        }
+        // the sign is start at below and isn't at above
+        while &above - &below > BigInt::one() {
+            let middle = (&below + &above) >> 1;
+            if sign(&evaluate(poly, &middle)) == start {
+                below = middle;
+            } else {
+                above = middle;
+            }
+        }
+        found.push(below);
+    }
+    let end = evaluate(poly, hi);
+    if end.is_zero() {
+        found.push(hi.clone());
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// the integer roots in [lo, hi] of a polynomial with coefficients lowest power first
+pub fn integer_roots(poly: &[BigInt], lo: &BigInt, hi: &BigInt) -> Vec<BigInt> {
+    let mut roots = crossings(poly, lo, hi)
+        .into_iter()
+        .flat_map(|t| [&t + 1, t])
+        .filter(|t| t <= hi && evaluate(poly, t).is_zero())
+        .collect::<Vec<_>>();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vectors(rows: &[&[i64]]) -> Vec<Vec<BigInt>> {
+        rows.iter()
+            .map(|row| row.iter().map(|&x| BigInt::from(x)).collect())
+            .collect()
+    }
+
+    fn numbers(xs: &[i64]) -> Vec<BigInt> {
+        xs.iter().map(|&x| BigInt::from(x)).collect()
+    }
+
+    #[test]
+    fn test_lll() {
+        // the example on Wikipedia
+        let mut basis = vectors(&[&[1, 1, 1], &[-1, 0, 2], &[3, 5, 6]]);
+        lll(&mut basis).unwrap();
+        assert_eq!(basis, vectors(&[&[0, 1, 0], &[1, 0, 1], &[-1, 0, 2]]));
+
+        // a knapsack-ish one, where the short vector is hiding behind big numbers
+        let mut basis = vectors(&[
+            &[1, 0, 0, 0, 1_000_003 * 17],
+            &[0, 1, 0, 0, 1_000_003 * 23],
+            &[0, 0, 1, 0, 1_000_003 * 41],
+            &[0, 0, 0, 1, 1_000_003 * -81],
+        ]);
+        lll(&mut basis).unwrap();
+        // 1*17 + 1*23 + 1*41 - 1*81 = 0
+        let shortest = basis[0].iter().map(|x| x.abs()).collect::<Vec<_>>();
+        assert_eq!(shortest, numbers(&[1, 1, 1, 1, 0]));
+
+        assert!(lll(&mut vectors(&[&[1, 2], &[2, 4]])).is_err());
+        assert!(lll(&mut []).is_ok());
+    }
+
+    #[test]
+    fn test_integer_roots() {
+        let lo = BigInt::from(-1000);
+        let hi = BigInt::from(1000);
+        // (x - 3)(x + 5)(x - 700) = x^3 - 698x^2 - 1415x + 10500
+        let poly = numbers(&[10500, -1415, -698, 1]);
+        assert_eq!(integer_roots(&poly, &lo, &hi), numbers(&[-5, 3, 700]));
+        assert_eq!(
+            integer_roots(&poly, &BigInt::from(0), &BigInt::from(700)),
+            numbers(&[3, 700])
+        );
+        // (2x - 1)(x - 10)^2: one root that isn't an integer, and a double one
+        let poly = numbers(&[-100, 220, -41, 2]);
+        assert_eq!(integer_roots(&poly, &lo, &hi), numbers(&[10]));
+        // x^2 + 1
+        assert!(integer_roots(&numbers(&[1, 0, 1]), &lo, &hi).is_empty());
+        assert!(integer_roots(&numbers(&[0]), &lo, &hi).is_empty());
+        assert_eq!(
+            evaluate(&numbers(&[1, 2, 3]), &BigInt::from(10)),
+            BigInt::from(321)
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// MGF1, the mask generation function OAEP and PSS share (RFC 8017 B.2.1): hash the seed with a
+// four byte big endian counter after it, counting from 0, and stick the hashes together until
+// there are enough bytes.
+
+use crate::hashes::sha1::sha1;
+use crate::hashes::sha256::sha256_bytes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hash {
+    Sha1,
+    Sha256,
+}
+
+impl Hash {
+    pub fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::Sha1 => sha1(bytes).to_vec(),
+            Hash::Sha256 => sha256_bytes(bytes).to_vec(),
+        }
+    }
+
+    /// in bytes
+    pub fn output_len(&self) -> usize {
+        match self {
+            Hash::Sha1 => 20,
+            Hash::Sha256 => 32,
+        }
+    }
+}
+
+pub fn mgf1(hash: Hash, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len + hash.output_len());
+    let mut block = seed.to_vec();
+    for counter in 0u32.. {
+        if mask.len() >= len {
+            break;
+        }
+        block.truncate(seed.len());
+        block.extend_from_slice(&counter.to_be_bytes());
+        mask.extend(hash.digest(&block));
+    }
+    mask.truncate(len);
+    mask
+}
+
+/// xor the mask into bytes, in place
+pub fn apply_mask(bytes: &mut [u8], mask: &[u8]) {
+    for (b, m) in bytes.iter_mut().zip(mask) {
+        *b ^= m;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptopal_util::bytes_to_hex;
+
+    #[test]
+    fn test_mgf1() {
+        assert_eq!(bytes_to_hex(&mgf1(Hash::Sha1, b"foo", 3)), "1ac907");
+        assert_eq!(
+            bytes_to_hex(&mgf1(Hash::Sha1, b"bar", 50)),
+            "bc0c655e016bc2931d85a2e675181adcef7f581f76df2739da74faac41627be2f7f415c89e983fd0ce80ced9878641cb4876"
+        );
+        assert_eq!(
+            bytes_to_hex(&mgf1(Hash::Sha256, b"bar", 50)),
+            "382576a7841021cc28fc4c0948753fb8312090cea942ea4c4e735d10dc724b155f9f6069f289d61daca0cb814502ef04eae1"
+        );
+        assert!(mgf1(Hash::Sha256, b"bar", 0).is_empty());
+    }
+}
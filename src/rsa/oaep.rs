@@ -0,0 +1,386 @@
+// RSAES-OAEP (RFC 8017 section 7.1), the padding to use instead of textbook RSA or PKCS#1 v1.5.
+//
+// For a k byte modulus and an hLen byte hash, the block that gets encrypted is
+//
+//   EM = 0x00 || maskedSeed || maskedDB
+//   DB = Hash(label) || 0x00 ... 0x00 || 0x01 || M
+//   maskedDB = DB xor MGF1(seed), maskedSeed = seed xor MGF1(maskedDB)
+//
+// where the seed is hLen random bytes. Decrypting undoes the masks and checks the leading zero,
+// the label hash and the 0x01.
+//
+// The RFC is firm that a decryptor mustn't let on which check failed, and Manger (CRYPTO 2001)
+// showed why. With B = 2^(8(k-1)), a decryptor that says "the first byte wasn't zero" tells you
+// whether m * f mod n is below B, for any f you like: send c * f^e. Every answer halves the range
+// m could be in, so about log2(n) questions later you have m:
+//
+//  1. double f1 until f1 * m >= B. then f1/2 * m is in [B/2, B)
+//  2. start f2 at floor((n + B) / B) * f1/2, a bit less than n / m, and add f1/2 until f2 * m is
+//     below B again. it's only done that by wrapping, so f2 * m is in [n, n + B)
+//  3. now m is in [n / f2, (n + B) / f2). pick f3 so that f3 * m is about i*n + B for some i,
+//     which the oracle says is above or below B, and keep the half m is in. repeat until there's
+//     only one number left
+//
+// Step 2 needs 2B < n, which is true unless n's top byte is 1.
+
+use super::mgf1::{apply_mask, mgf1, Hash};
+use super::{i2osp, RsaKey, RsaPubKey};
+use anyhow::{anyhow, Result};
+use num::{BigUint, Integer, One};
+use rand::RngCore;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Oaep {
+    pub hash: Hash,
+    pub mgf1_hash: Hash,
+    pub label: Vec<u8>,
+}
+
+/// what went wrong decoding. only oaep_decrypt_leaky says which
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OaepError {
+    FirstByteNotZero,
+    BadPadding,
+}
+
+impl fmt::Display for OaepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OaepError::FirstByteNotZero => write!(f, "first byte of the OAEP block isn't zero"),
+            OaepError::BadPadding => write!(f, "bad OAEP padding"),
+        }
+    }
+}
+
+impl std::error::Error for OaepError {}
+
+impl Oaep {
+    /// the same hash for MGF1, and no label
+    pub fn new(hash: Hash) -> Oaep {
+        Oaep {
+            hash,
+            mgf1_hash: hash,
+            label: vec![],
+        }
+    }
+
+    pub fn with_label(mut self, label: &[u8]) -> Oaep {
+        self.label = label.to_vec();
+        self
+    }
+
+    /// the longest message that fits in k bytes (0 if nothing does)
+    pub fn max_message_len(&self, k: usize) -> usize {
+        k.saturating_sub(2 * self.hash.output_len() + 2)
+    }
+
+    /// EM, k bytes of it
+    pub fn encode(&self, message: &[u8], seed: &[u8], k: usize) -> Result<Vec<u8>> {
+        let h_len = self.hash.output_len();
+        if seed.len() != h_len {
+            return Err(anyhow!("the seed should be {h_len} bytes"));
+        }
+        if k < 2 * h_len + 2 || message.len() > self.max_message_len(k) {
+            return Err(anyhow!(
+                "a {} byte message won't fit in {k} bytes of OAEP",
+                message.len()
+            ));
+        }
+        let mut db = self.hash.digest(&self.label);
+        db.resize(k - h_len - 2 - message.len(), 0);
+        db.push(1);
+        db.extend_from_slice(message);
+        let db_mask = mgf1(self.mgf1_hash, seed, db.len());
+        apply_mask(&mut db, &db_mask);
+        let mut masked_seed = seed.to_vec();
+        apply_mask(&mut masked_seed, &mgf1(self.mgf1_hash, &db, h_len));
+
+        let mut em = vec![0];
+        em.extend(masked_seed);
+        em.extend(db);
+        Ok(em)
+    }
+
+    /// M back out of EM
+    pub fn decode(&self, em: &[u8]) -> Result<Vec<u8>, OaepError> {
+        let h_len = self.hash.output_len();
+        if em.len() < 2 * h_len + 2 {
+            return Err(OaepError::BadPadding);
+        }
+        if em[0] != 0 {
+            return Err(OaepError::FirstByteNotZero);
+        }
+        let (masked_seed, masked_db) = em[1..].split_at(h_len);
+        let mut seed = masked_seed.to_vec();
+        apply_mask(&mut seed, &mgf1(self.mgf1_hash, masked_db, h_len));
+        let mut db = masked_db.to_vec();
+        apply_mask(&mut db, &mgf1(self.mgf1_hash, &seed, masked_db.len()));
+
+        let (label_hash, rest) = db.split_at(h_len);
+        if label_hash != self.hash.digest(&self.label) {
+            return Err(OaepError::BadPadding);
+        }
+        match rest.iter().position(|&b| b != 0) {
+            Some(i) if rest[i] == 1 => Ok(rest[i + 1..].to_vec()),
+            _ => Err(OaepError::BadPadding),
+        }
+    }
+}
+
+impl RsaPubKey {
+    pub fn oaep_encrypt(&self, oaep: &Oaep, message: &[u8]) -> Result<Vec<u8>> {
+        let mut seed = vec![0; oaep.hash.output_len()];
+        rand::thread_rng().fill_bytes(&mut seed);
+        self.oaep_encrypt_with_seed(oaep, message, &seed)
+    }
+
+    /// with a seed of our choosing, for test vectors. don't reuse seeds
+    pub fn oaep_encrypt_with_seed(
+        &self,
+        oaep: &Oaep,
+        message: &[u8],
+        seed: &[u8],
+    ) -> Result<Vec<u8>> {
+        let k = self.modulus_len();
+        let em = oaep.encode(message, seed, k)?;
+        let c = self.encrypt(&BigUint::from_bytes_be(&em))?;
+        i2osp(&c, k)
+    }
+}
+
+impl RsaKey {
+    /// the same error whatever went wrong, as the RFC says
+    pub fn oaep_decrypt(&self, oaep: &Oaep, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        self.oaep_decrypt_leaky(oaep, ciphertext)
+            .map_err(|_| anyhow!("decryption error"))
+    }
+
+    /// says whether the first byte wasn't zero, which is all Manger's attack needs
+    pub fn oaep_decrypt_leaky(&self, oaep: &Oaep, ciphertext: &[u8]) -> Result<Vec<u8>, OaepError> {
+        let k = self.get_public_key().modulus_len();
+        let c = BigUint::from_bytes_be(ciphertext);
+        if ciphertext.len() != k || c >= self.modulus {
+            return Err(OaepError::BadPadding);
+        }
+        let m = self.decrypt(&c).map_err(|_| OaepError::BadPadding)?;
+        let em = i2osp(&m, k).map_err(|_| OaepError::BadPadding)?;
+        oaep.decode(&em)
+    }
+}
+
+/// anything that decrypts RSA-OAEP and lets slip whether the first byte was zero
+pub trait MangerOracle {
+    fn first_byte_is_zero(&self, ciphertext: &[u8]) -> bool;
+}
+
+pub struct MangerAttacker<'a, O: MangerOracle + ?Sized> {
+    oracle: &'a O,
+    public_key: RsaPubKey,
+    queries: usize,
+}
+
+impl<'a, O: MangerOracle + ?Sized> MangerAttacker<'a, O> {
+    pub fn new(oracle: &'a O, public_key: RsaPubKey) -> MangerAttacker<'a, O> {
+        MangerAttacker {
+            oracle,
+            public_key,
+            queries: 0,
+        }
+    }
+
+    /// how many times we've asked the oracle so far
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    // is f * m mod n below B?
+    fn ask(&mut self, c: &BigUint, f: &BigUint) -> Result<bool> {
+        self.queries += 1;
+        let n = &self.public_key.modulus;
+        let forged = c * self.public_key.encrypt(&(f % n))? % n;
+        Ok(self
+            .oracle
+            .first_byte_is_zero(&i2osp(&forged, self.public_key.modulus_len())?))
+    }
+
+    /// EM, the whole padded block, for a ciphertext the oracle would accept
+    pub fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let n = self.public_key.modulus.clone();
+        let k = self.public_key.modulus_len();
+        let b = BigUint::one() << (8 * (k - 1));
+        if n <= &b * 2u32 {
+            return Err(anyhow!("Manger's attack needs n > 2B"));
+        }
+        let c = BigUint::from_bytes_be(ciphertext);
+        if !self.ask(&c, &BigUint::one())? {
+            return Err(anyhow!("that ciphertext doesn't start with a zero byte"));
+        }
+
+        // 1
+        let mut f1 = BigUint::from(2u32);
+        while self.ask(&c, &f1)? {
+            f1 <<= 1;
+        }
+        // 2
+        let half_f1 = &f1 >> 1;
+        let mut f2 = (&n + &b) / &b * &half_f1;
+        while !self.ask(&c, &f2)? {
+            f2 += &half_f1;
+        }
+        // 3
+        let mut m_min = n.div_ceil(&f2);
+        let mut m_max = (&n + &b) / &f2;
+        while m_min < m_max {
+            let f_tmp = &b * 2u32 / (&m_max - &m_min);
+            let i_n = &f_tmp * &m_min / &n * &n;
+            let f3 = i_n.div_ceil(&m_min);
+            if self.ask(&c, &f3)? {
+                m_max = (&i_n + &b) / &f3;
+            } else {
+                m_min = (&i_n + &b).div_ceil(&f3);
+            }
+        }
+        i2osp(&m_min, k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptopal_util::hex_to_bytes;
+
+    fn hex(s: &str) -> Vec<u8> {
+        hex_to_bytes(s.replace(' ', "")).unwrap()
+    }
+
+    fn big(s: &str) -> BigUint {
+        BigUint::from_bytes_be(&hex(s))
+    }
+
+    #[test]
+    fn test_rsa_labs_vector() {
+        // the worked RSAES-OAEP example from the PKCS #1 v2.1 test vectors (oaep-int.txt)
+        let key = RsaKey::from_primes(
+            big("eecfae81b1b9b3c908810b10a1b5600199eb9f44aef4fda493b81a9e3d84f632124ef0236e5d1e3b7e28fae7aa040a2d5b252176459d1f397541ba2a58fb6599"),
+            big("c97fb1f027f453f6341233eaaad1d9353f6c42d08866b1d05a0f2035028b9d869840b41666b42e92ea0da3b43204b5cfce3352524d0416a5a441e700af461503"),
+            BigUint::from(0x11u32),
+        )
+        .unwrap();
+        assert_eq!(
+            key.modulus,
+            big("bbf82f090682ce9c2338ac2b9da871f7368d07eed41043a440d6b6f07454f51fb8dfbaaf035c02ab61ea48ceeb6fcd4876ed520d60e1ec4619719d8a5b8b807fafb8e0a3dfc737723ee6b4b7d93a2584ee6a649d060953748834b2454598394ee0aab12d7b61a51f527a9a41f6c1687fe2537298ca2a8f5946f8e5fd091dbdcb")
+        );
+        let oaep = Oaep::new(Hash::Sha1);
+        let message = hex("d436e99569fd32a7c8a05bbc90d32c49");
+        let seed = hex("aafd12f659cae63489b479e5076ddec2f06cb58f");
+        let ciphertext = hex("1253e04dc0a5397bb44a7ab87e9bf2a039a33d1e996fc82a94ccd30074c95df763722017069e5268da5d1c0b4f872cf653c11df82314a67968dfeae28def04bb6d84b1c31d654a1970e5783bd6eb96a024c2ca2f4a90fe9f2ef5c9c140e5bb48da9536ad8700c84fc9130adea74e558d51a74ddf85d8b50de96838d6063e0955");
+        let public = key.get_public_key();
+        assert_eq!(
+            public
+                .oaep_encrypt_with_seed(&oaep, &message, &seed)
+                .unwrap(),
+            ciphertext
+        );
+        assert_eq!(key.oaep_decrypt(&oaep, &ciphertext).unwrap(), message);
+    }
+
+    #[test]
+    fn test_openssl_sha256_and_label() {
+        // openssl pkeyutl -encrypt -pkeyopt rsa_padding_mode:oaep -pkeyopt rsa_oaep_md:sha256 ...
+        // of "attack at dawn", with our test key
+        let key =
+            RsaKey::from_pem(&std::fs::read_to_string("./data/rsa_pkcs8.pem").unwrap()).unwrap();
+        let labelled = Oaep::new(Hash::Sha256).with_label(b"cryptopals");
+        let ciphertext = hex("65a4fe0943222a1af8c8f080480d2bc0914652b6fd247407b5be6cff7cb089232809bd311367aa04632fb43b7dfb9d6341ff99411cda971ddb93e71afedaeeb4276271705fcf807bf05ceb76b02f6892fdae30168500619564ddeec94901f732cad285da6b1b9fec74c548fa55623a9c3499ef3c048aad7778aede065bf2ddb3");
+        assert_eq!(
+            key.oaep_decrypt(&labelled, &ciphertext).unwrap(),
+            b"attack at dawn"
+        );
+        // the wrong label (or none) is just a decryption error
+        assert!(key
+            .oaep_decrypt(&Oaep::new(Hash::Sha256), &ciphertext)
+            .is_err());
+
+        // SHA-256 for the label hash, SHA-1 for MGF1
+        let mixed = Oaep {
+            mgf1_hash: Hash::Sha1,
+            ..Oaep::new(Hash::Sha256)
+        };
+        let ciphertext = hex("0cc20748927bf2a40876a303c08ba2da1d8e7ec72277cbb64f2506e3dcb79f79a151aba6d04dc0ace3fdc1b4b7cc2a630abe253c0e796d874c7324727dbb7200a7d5ca7c1f60d00db79e08652237ca093c451161a2855cf1091f41ab78992f1031e9adaafb6fed207db9251787d0f84fcd91adeccb44c825338cf4597c67ae2b");
+        assert_eq!(
+            key.oaep_decrypt(&mixed, &ciphertext).unwrap(),
+            b"attack at dawn"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_and_limits() {
+        let key = RsaKey::new(256);
+        let public = key.get_public_key();
+        let oaep = Oaep::new(Hash::Sha1).with_label(b"some label");
+        let longest = vec![b'x'; oaep.max_message_len(64)];
+        assert_eq!(longest.len(), 22);
+        for message in [&b""[..], b"hi mom", &longest] {
+            let c = public.oaep_encrypt(&oaep, message).unwrap();
+            assert_eq!(c.len(), 64);
+            assert_eq!(key.oaep_decrypt(&oaep, &c).unwrap(), message);
+        }
+        // randomized
+        assert_ne!(
+            public.oaep_encrypt(&oaep, b"hi mom").unwrap(),
+            public.oaep_encrypt(&oaep, b"hi mom").unwrap()
+        );
+        assert!(public.oaep_encrypt(&oaep, &[0; 23]).is_err());
+        // SHA-256 needs 66 bytes just for padding
+        assert!(public.oaep_encrypt(&Oaep::new(Hash::Sha256), b"").is_err());
+
+        let mut c = public.oaep_encrypt(&oaep, b"hi mom").unwrap();
+        c[10] ^= 1;
+        assert!(key.oaep_decrypt(&oaep, &c).is_err());
+        assert!(key.oaep_decrypt(&oaep, &c[1..]).is_err());
+    }
+
+    struct Server {
+        key: RsaKey,
+        oaep: Oaep,
+    }
+
+    impl MangerOracle for Server {
+        fn first_byte_is_zero(&self, ciphertext: &[u8]) -> bool {
+            self.key.oaep_decrypt_leaky(&self.oaep, ciphertext) != Err(OaepError::FirstByteNotZero)
+        }
+    }
+
+    #[test]
+    fn test_manger() {
+        let server = Server {
+            key: RsaKey::new(384),
+            oaep: Oaep::new(Hash::Sha256),
+        };
+        let public = server.key.get_public_key();
+        let secret = b"meet me by the old oak tree";
+        let ciphertext = public.oaep_encrypt(&server.oaep, secret).unwrap();
+
+        let mut attacker = MangerAttacker::new(&server, public.clone());
+        let em = attacker.decrypt(&ciphertext).unwrap();
+        assert_eq!(server.oaep.decode(&em).unwrap(), secret);
+        println!(
+            "{} oracle queries for a {} bit modulus",
+            attacker.queries(),
+            public.modulus.bits()
+        );
+        // about one per bit, plus however many steps 1 and 2 took
+        assert!(attacker.queries() < public.modulus.bits() as usize * 2);
+
+        // the oracle has to accept the ciphertext to begin with
+        let not_ours = i2osp(
+            &public.encrypt(&(&public.modulus - 1u32)).unwrap(),
+            public.modulus_len(),
+        )
+        .unwrap();
+        assert!(MangerAttacker::new(&server, public)
+            .decrypt(&not_ours)
+            .is_err());
+    }
+}
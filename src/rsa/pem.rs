@@ -0,0 +1,275 @@
+// RSA keys in the formats real tools use, so the attacks can go after keys from OpenSSL.
+//
+//  - PKCS#1 (RFC 8017 appendix A.1): RSAPublicKey is SEQUENCE { n, e }, RSAPrivateKey is
+//    SEQUENCE { 0, n, e, d, p, q, dP, dQ, qInv }. PEM labels "RSA PUBLIC KEY" and
+//    "RSA PRIVATE KEY".
+//  - SubjectPublicKeyInfo (RFC 5280): SEQUENCE { AlgorithmIdentifier, BIT STRING holding the
+//    PKCS#1 public key }. PEM label "PUBLIC KEY".
+//  - PKCS#8 (RFC 5208): SEQUENCE { 0, AlgorithmIdentifier, OCTET STRING holding the PKCS#1 private
+//    key }. PEM label "PRIVATE KEY".
+//
+// The AlgorithmIdentifier is SEQUENCE { rsaEncryption, NULL } for all of them. PEM is base64 DER,
+// 64 characters a line, between BEGIN and END lines with the label.
+
+use super::der::{self, Asn1, Oid};
+use super::{RsaKey, RsaPubKey};
+use crate::cryptopal_util;
+use anyhow::{anyhow, Result};
+use num::BigUint;
+
+const RSA_ENCRYPTION: [u64; 7] = [1, 2, 840, 113549, 1, 1, 1];
+
+fn algorithm_identifier() -> Asn1 {
+    Asn1::Sequence(vec![
+        Asn1::ObjectIdentifier(Oid::new(&RSA_ENCRYPTION).unwrap()),
+        Asn1::Null,
+    ])
+}
+
+fn check_algorithm_identifier(value: &Asn1) -> Result<()> {
+    match value.as_sequence()? {
+        [Asn1::ObjectIdentifier(oid), rest @ ..]
+            if oid.arcs() == RSA_ENCRYPTION && matches!(rest, [] | [Asn1::Null]) =>
+        {
+            Ok(())
+        }
+        _ => Err(anyhow!("that isn't an rsaEncryption key")),
+    }
+}
+
+/// base64 the DER between BEGIN and END lines
+pub fn encode(label: &str, der: &[u8]) -> String {
+    let base64 = cryptopal_util::bytes_to_b64(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in base64.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+    pem
+}
+
+/// the first PEM block in the text: (label, DER)
+pub fn decode(text: &str) -> Result<(String, Vec<u8>)> {
+    let mut lines = text.lines().map(str::trim);
+    let label = lines
+        .by_ref()
+        .find_map(|line| line.strip_prefix("-----BEGIN ")?.strip_suffix("-----"))
+        .ok_or_else(|| anyhow!("no BEGIN line"))?
+        .to_string();
+    let end = format!("-----END {label}-----");
+    let mut base64 = String::new();
+    for line in lines.by_ref() {
+        if line == end {
+            return Ok((label, cryptopal_util::b64_to_bytes(base64)?));
+        }
+        // encrypted keys have headers like Proc-Type, which we can't do anything with
+        if line.contains(':') {
+            return Err(anyhow!("PEM headers (encrypted keys?) aren't supported"));
+        }
+        base64.push_str(line);
+    }
+    Err(anyhow!("no {end} line"))
+}
+
+fn integers<const N: usize>(items: &[Asn1]) -> Result<[BigUint; N]> {
+    if items.len() != N {
+        return Err(anyhow!("wanted {N} INTEGERs, got {} things", items.len()));
+    }
+    let mut out = std::array::from_fn(|_| BigUint::default());
+    for (slot, item) in out.iter_mut().zip(items) {
+        *slot = item.as_integer()?.clone();
+    }
+    Ok(out)
+}
+
+impl RsaPubKey {
+    pub fn to_pkcs1_der(&self) -> Vec<u8> {
+        der::encode(&Asn1::Sequence(vec![
+            Asn1::Integer(self.modulus.clone()),
+            Asn1::Integer(self.public_exponent.clone()),
+        ]))
+    }
+
+    pub fn from_pkcs1_der(bytes: &[u8]) -> Result<RsaPubKey> {
+        let [modulus, public_exponent] = integers(der::decode(bytes)?.as_sequence()?)?;
+        Ok(RsaPubKey {
+            modulus,
+            public_exponent,
+        })
+    }
+
+    pub fn to_spki_der(&self) -> Vec<u8> {
+        der::encode(&Asn1::Sequence(vec![
+            algorithm_identifier(),
+            Asn1::BitString(self.to_pkcs1_der()),
+        ]))
+    }
+
+    pub fn from_spki_der(bytes: &[u8]) -> Result<RsaPubKey> {
+        match der::decode(bytes)?.as_sequence()? {
+            [algorithm, key] => {
+                check_algorithm_identifier(algorithm)?;
+                RsaPubKey::from_pkcs1_der(key.as_bit_string()?)
+            }
+            _ => Err(anyhow!("that isn't a SubjectPublicKeyInfo")),
+        }
+    }
+
+    pub fn to_pkcs1_pem(&self) -> String {
+        encode("RSA PUBLIC KEY", &self.to_pkcs1_der())
+    }
+
+    pub fn to_spki_pem(&self) -> String {
+        encode("PUBLIC KEY", &self.to_spki_der())
+    }
+
+    /// either kind of public key PEM
+    pub fn from_pem(text: &str) -> Result<RsaPubKey> {
+        match decode(text)? {
+            (label, der) if label == "RSA PUBLIC KEY" => RsaPubKey::from_pkcs1_der(&der),
+            (label, der) if label == "PUBLIC KEY" => RsaPubKey::from_spki_der(&der),
+            (label, _) => Err(anyhow!("{label} isn't a public key")),
+        }
+    }
+}
+
+impl RsaKey {
+    pub fn to_pkcs1_der(&self) -> Vec<u8> {
+        der::encode(&Asn1::Sequence(
+            [
+                &BigUint::default(),
+                &self.modulus,
+                &self.public_exponent,
+                &self.private_exponent,
+                &self.p,
+                &self.q,
+                &self.dp,
+                &self.dq,
+                &self.qinv,
+            ]
+            .map(|n| Asn1::Integer(n.clone()))
+            .to_vec(),
+        ))
+    }
+
+    /// takes the key as it is: call validate if you don't trust where it came from
+    pub fn from_pkcs1_der(bytes: &[u8]) -> Result<RsaKey> {
+        let [version, modulus, public_exponent, private_exponent, p, q, dp, dq, qinv] =
+            integers(der::decode(bytes)?.as_sequence()?)?;
+        if version != BigUint::default() {
+            return Err(anyhow!("only two prime keys (version 0)"));
+        }
+        Ok(RsaKey {
+            p,
+            q,
+            modulus,
+            public_exponent,
+            private_exponent,
+            dp,
+            dq,
+            qinv,
+        })
+    }
+
+    pub fn to_pkcs8_der(&self) -> Vec<u8> {
+        der::encode(&Asn1::Sequence(vec![
+            Asn1::integer(0),
+            algorithm_identifier(),
+            Asn1::OctetString(self.to_pkcs1_der()),
+        ]))
+    }
+
+    pub fn from_pkcs8_der(bytes: &[u8]) -> Result<RsaKey> {
+        match der::decode(bytes)?.as_sequence()? {
+            [Asn1::Integer(version), algorithm, key] if *version == BigUint::default() => {
+                check_algorithm_identifier(algorithm)?;
+                RsaKey::from_pkcs1_der(key.as_octet_string()?)
+            }
+            _ => Err(anyhow!("that isn't a PKCS#8 private key")),
+        }
+    }
+
+    pub fn to_pkcs1_pem(&self) -> String {
+        encode("RSA PRIVATE KEY", &self.to_pkcs1_der())
+    }
+
+    pub fn to_pkcs8_pem(&self) -> String {
+        encode("PRIVATE KEY", &self.to_pkcs8_der())
+    }
+
+    /// either kind of private key PEM
+    pub fn from_pem(text: &str) -> Result<RsaKey> {
+        match decode(text)? {
+            (label, der) if label == "RSA PRIVATE KEY" => RsaKey::from_pkcs1_der(&der),
+            (label, der) if label == "PRIVATE KEY" => RsaKey::from_pkcs8_der(&der),
+            (label, _) => Err(anyhow!("{label} isn't a private key")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(filename: &str) -> String {
+        std::fs::read_to_string(format!("./data/{filename}")).unwrap()
+    }
+
+    #[test]
+    fn test_openssl_keys() {
+        // all four files are the same key, from openssl genpkey -algorithm RSA
+        let pkcs8 = read("rsa_pkcs8.pem");
+        let pkcs1 = read("rsa_pkcs1.pem");
+        let spki = read("rsa_spki.pem");
+        let pkcs1_pub = read("rsa_pkcs1_pub.pem");
+
+        let key = RsaKey::from_pem(&pkcs8).unwrap();
+        key.validate().unwrap();
+        assert_eq!(key.modulus.bits(), 1024);
+        assert_eq!(RsaKey::from_pem(&pkcs1).unwrap(), key);
+        assert_eq!(key.to_pkcs8_pem(), pkcs8);
+        assert_eq!(key.to_pkcs1_pem(), pkcs1);
+
+        let public = RsaPubKey::from_pem(&spki).unwrap();
+        assert_eq!(public, key.get_public_key());
+        assert_eq!(RsaPubKey::from_pem(&pkcs1_pub).unwrap(), public);
+        assert_eq!(public.to_spki_pem(), spki);
+        assert_eq!(public.to_pkcs1_pem(), pkcs1_pub);
+
+        // and they work
+        let m = BigUint::from(1234567u32);
+        let c = public.encrypt(&m).unwrap();
+        assert_eq!(key.decrypt(&c).unwrap(), m);
+    }
+
+    #[test]
+    fn test_our_keys() {
+        let key = RsaKey::new(256);
+        assert_eq!(RsaKey::from_pem(&key.to_pkcs8_pem()).unwrap(), key);
+        assert_eq!(RsaKey::from_pem(&key.to_pkcs1_pem()).unwrap(), key);
+        let public = key.get_public_key();
+        assert_eq!(RsaPubKey::from_pem(&public.to_spki_pem()).unwrap(), public);
+        assert_eq!(RsaPubKey::from_pem(&public.to_pkcs1_pem()).unwrap(), public);
+    }
+
+    #[test]
+    fn test_wrong_things() {
+        assert!(RsaKey::from_pem(&read("rsa_spki.pem")).is_err());
+        assert!(RsaPubKey::from_pem(&read("rsa_pkcs8.pem")).is_err());
+        assert!(RsaKey::from_pem("nothing to see here").is_err());
+        let unterminated = read("rsa_pkcs1.pem").replace("-----END RSA PRIVATE KEY-----", "");
+        assert!(RsaKey::from_pem(&unterminated).is_err());
+        // a PUBLIC KEY that's something other than RSA
+        let not_rsa = encode(
+            "PUBLIC KEY",
+            &der::encode(&Asn1::Sequence(vec![
+                Asn1::Sequence(vec![Asn1::ObjectIdentifier(
+                    Oid::new(&[1, 2, 840, 10045, 2, 1]).unwrap(),
+                )]),
+                Asn1::BitString(vec![4, 1, 2]),
+            ])),
+        );
+        assert!(RsaPubKey::from_pem(&not_rsa).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Polynomials mod n, as Vec<BigUint> with the coefficients lowest power first and no zeros on the
+// top end, so the zero polynomial is []. Same layout as lattice.rs, but everything reduced mod n.
+//
+// n isn't a prime, so Z_n isn't a field and dividing by a leading coefficient can fail. When it
+// does, that coefficient shares a factor with n, which is much better news than a polynomial gcd,
+// so the error says what it is.
+
+use super::invmod;
+use anyhow::{anyhow, Result};
+use num::{BigUint, Integer, Zero};
+
+fn trimmed(mut poly: Vec<BigUint>) -> Vec<BigUint> {
+    while poly.last().is_some_and(Zero::is_zero) {
+        poly.pop();
+    }
+    poly
+}
+
+/// poly with every coefficient reduced mod n and the zeros on top taken off
+pub fn reduce(poly: &[BigUint], n: &BigUint) -> Vec<BigUint> {
+    trimmed(poly.iter().map(|c| c % n).collect())
+}
+
+pub fn multiply(a: &[BigUint], b: &[BigUint], n: &BigUint) -> Vec<BigUint> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut product = vec![BigUint::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] = (&product[i + j] + x * y) % n;
+        }
+    }
+    trimmed(product)
+}
+
+fn leading_inverse(poly: &[BigUint], n: &BigUint) -> Result<BigUint> {
+    let leading = poly
+        .last()
+        .ok_or_else(|| anyhow!("dividing by the zero polynomial"))?;
+    invmod(leading.clone(), n.clone()).ok_or_else(|| {
+        anyhow!(
+            "a leading coefficient isn't invertible: n has the factor {}",
+            leading.gcd(n)
+        )
+    })
+}
+
+/// (quotient, remainder) with a = quotient * b + remainder and the remainder's degree below b's
+pub fn divide(a: &[BigUint], b: &[BigUint], n: &BigUint) -> Result<(Vec<BigUint>, Vec<BigUint>)> {
+    let b = reduce(b, n);
+    let inverse = leading_inverse(&b, n)?;
+    let mut remainder = reduce(a, n);
+    if remainder.len() < b.len() {
+        return Ok((vec![], remainder));
+    }
+    let mut quotient = vec![BigUint::zero(); remainder.len() - b.len() + 1];
+    for shift in (0..quotient.len()).rev() {
+        let top = remainder.pop().unwrap();
+        let factor = top * &inverse % n;
+        // take factor * x^shift * b off, except the top term, which is already gone
+        for (r, c) in remainder[shift..].iter_mut().zip(&b) {
+            *r = (&*r + n - &factor * c % n) % n;
+        }
+        quotient[shift] = factor;
+    }
+    Ok((trimmed(quotient), trimmed(remainder)))
+}
+
+/// the monic gcd of a and b, or [] if they're both zero
+pub fn gcd(a: &[BigUint], b: &[BigUint], n: &BigUint) -> Result<Vec<BigUint>> {
+    let mut a = reduce(a, n);
+    let mut b = reduce(b, n);
+    while !b.is_empty() {
+        let (_, remainder) = divide(&a, &b, n)?;
+        a = std::mem::replace(&mut b, remainder);
+    }
+    if a.is_empty() {
+        return Ok(a);
+    }
+    let inverse = leading_inverse(&a, n)?;
+    Ok(a.iter().map(|c| c * &inverse % n).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(coefficients: &[u32]) -> Vec<BigUint> {
+        coefficients.iter().map(|&c| BigUint::from(c)).collect()
+    }
+
+    #[test]
+    fn test_multiply_and_divide() {
+        let n = BigUint::from(101u32);
+        // (x + 2)(3x^2 + 4) = 3x^3 + 6x^2 + 4x + 8
+        let product = multiply(&poly(&[2, 1]), &poly(&[4, 0, 3]), &n);
+        assert_eq!(product, poly(&[8, 4, 6, 3]));
+        assert!(multiply(&product, &[], &n).is_empty());
+        // (x + 100)(x + 1) = x^2 - 1
+        assert_eq!(
+            multiply(&poly(&[100, 1]), &poly(&[1, 1]), &n),
+            poly(&[100, 0, 1])
+        );
+
+        let (quotient, remainder) = divide(&product, &poly(&[4, 0, 3]), &n).unwrap();
+        assert_eq!(quotient, poly(&[2, 1]));
+        assert!(remainder.is_empty());
+        // something that doesn't go in evenly, with a leading coefficient that isn't 1
+        let a = poly(&[7, 0, 5, 9, 1, 33]);
+        let b = poly(&[3, 8, 2]);
+        let (quotient, remainder) = divide(&a, &b, &n).unwrap();
+        assert!(remainder.len() < b.len());
+        let mut back = multiply(&quotient, &b, &n);
+        for (c, r) in back.iter_mut().zip(&remainder) {
+            *c = (&*c + r) % &n;
+        }
+        assert_eq!(back, a);
+        let (quotient, remainder) = divide(&b, &a, &n).unwrap();
+        assert!(quotient.is_empty());
+        assert_eq!(remainder, b);
+
+        assert!(divide(&a, &[], &n).is_err());
+        assert!(divide(&a, &poly(&[0, 0, 101]), &n).is_err());
+        // 6 isn't invertible mod 15
+        let error = divide(&a, &poly(&[1, 6]), &BigUint::from(15u32)).unwrap_err();
+        assert!(error.to_string().contains("factor 3"));
+    }
+
+    #[test]
+    fn test_gcd() {
+        let n = BigUint::from(1_000_003u32);
+        let common = poly(&[999_990, 1]); // x - 13
+        let a = multiply(&common, &poly(&[5, 3, 1]), &n);
+        let b = multiply(&common, &multiply(&poly(&[7, 1]), &poly(&[2, 9]), &n), &n);
+        assert_eq!(gcd(&a, &b, &n).unwrap(), common);
+        assert_eq!(gcd(&b, &a, &n).unwrap(), common);
+        // made monic
+        let doubled = multiply(&a, &poly(&[2]), &n);
+        assert_eq!(gcd(&doubled, &[], &n).unwrap(), a);
+        assert_eq!(
+            gcd(&poly(&[5, 3, 1]), &poly(&[7, 1]), &n).unwrap(),
+            poly(&[1])
+        );
+        assert!(gcd(&[], &[], &n).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+// RSASSA-PSS (RFC 8017 sections 8.1 and 9.1), the signature scheme to use instead of PKCS#1 v1.5.
+//
+// To sign M with an emBits = modBits - 1 bit encoding and an hLen byte hash:
+//
+//   H = Hash(0x00 * 8 || Hash(M) || salt)
+//   DB = 0x00 ... 0x00 || 0x01 || salt
+//   EM = (DB xor MGF1(H)) || H || 0xbc
+//
+// with the top 8 * emLen - emBits bits of EM cleared so it's below n. Then the signature is EM^d.
+// The verifier gets EM back with s^e, unmasks DB with the H it finds, reads the salt from the end
+// of DB and checks H is what it should be.
+//
+// There's nowhere for the garbage in Bleichenbacher's e=3 forgery (challenge 42) to go: the
+// verifier looks at every byte of EM, and the bytes that aren't fixed depend on a hash of
+// everything else. And EM always ends in 0xbc, which is 4 times an odd number, so it can't be a
+// cube that doesn't wrap n: an even cube is a multiple of 8.
+
+use super::mgf1::{apply_mask, mgf1, Hash};
+use super::{i2osp, RsaKey, RsaPubKey};
+use anyhow::{anyhow, Result};
+use num::BigUint;
+use rand::RngCore;
+
+const TRAILER: u8 = 0xbc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pss {
+    pub hash: Hash,
+    pub mgf1_hash: Hash,
+    /// in bytes
+    pub salt_len: usize,
+}
+
+impl Pss {
+    /// the same hash for MGF1, and a salt as long as the hash
+    pub fn new(hash: Hash) -> Pss {
+        Pss {
+            hash,
+            mgf1_hash: hash,
+            salt_len: hash.output_len(),
+        }
+    }
+
+    pub fn with_salt_len(mut self, salt_len: usize) -> Pss {
+        self.salt_len = salt_len;
+        self
+    }
+
+    // the bytes, and how many bits of the first byte have to be clear
+    fn em_len(em_bits: usize) -> (usize, u32) {
+        let em_len = em_bits.div_ceil(8);
+        (em_len, (8 * em_len - em_bits) as u32)
+    }
+
+    fn h(&self, message: &[u8], salt: &[u8]) -> Vec<u8> {
+        let mut m_prime = vec![0; 8];
+        m_prime.extend(self.hash.digest(message));
+        m_prime.extend_from_slice(salt);
+        self.hash.digest(&m_prime)
+    }
+
+    /// EM, for an em_bits bit number
+    pub fn encode(&self, message: &[u8], salt: &[u8], em_bits: usize) -> Result<Vec<u8>> {
+        let h_len = self.hash.output_len();
+        let (em_len, clear_bits) = Pss::em_len(em_bits);
+        if salt.len() != self.salt_len {
+            return Err(anyhow!("the salt should be {} bytes", self.salt_len));
+        }
+        if em_len < h_len + self.salt_len + 2 {
+            return Err(anyhow!(
+                "{em_bits} bits is too short for PSS with a {} byte salt",
+                self.salt_len
+            ));
+        }
+        let h = self.h(message, salt);
+        let mut db = vec![0; em_len - self.salt_len - h_len - 2];
+        db.push(1);
+        db.extend_from_slice(salt);
+        apply_mask(&mut db, &mgf1(self.mgf1_hash, &h, em_len - h_len - 1));
+        db[0] &= 0xff >> clear_bits;
+
+        let mut em = db;
+        em.extend(h);
+        em.push(TRAILER);
+        Ok(em)
+    }
+
+    /// is em what encode would have made of message, with some salt?
+    pub fn verify_encoding(&self, message: &[u8], em: &[u8], em_bits: usize) -> Result<()> {
+        let h_len = self.hash.output_len();
+        let (em_len, clear_bits) = Pss::em_len(em_bits);
+        if em.len() != em_len || em_len < h_len + self.salt_len + 2 {
+            return Err(anyhow!("inconsistent: wrong length"));
+        }
+        if em[em_len - 1] != TRAILER {
+            return Err(anyhow!("inconsistent: doesn't end in 0xbc"));
+        }
+        let (masked_db, h) = em[..em_len - 1].split_at(em_len - h_len - 1);
+        if masked_db[0] & !(0xff >> clear_bits) != 0 {
+            return Err(anyhow!("inconsistent: top bits are set"));
+        }
+        let mut db = masked_db.to_vec();
+        apply_mask(&mut db, &mgf1(self.mgf1_hash, h, masked_db.len()));
+        db[0] &= 0xff >> clear_bits;
+
+        let (padding, salt) = db.split_at(db.len() - self.salt_len);
+        match padding.split_last() {
+            Some((1, zeros)) if zeros.iter().all(|&b| b == 0) => {}
+            _ => return Err(anyhow!("inconsistent: bad padding")),
+        }
+        if self.h(message, salt) != h {
+            return Err(anyhow!("inconsistent: hash doesn't match"));
+        }
+        Ok(())
+    }
+}
+
+impl RsaKey {
+    pub fn pss_sign(&self, pss: &Pss, message: &[u8]) -> Result<Vec<u8>> {
+        let mut salt = vec![0; pss.salt_len];
+        rand::thread_rng().fill_bytes(&mut salt);
+        self.pss_sign_with_salt(pss, message, &salt)
+    }
+
+    /// with a salt of our choosing, for test vectors
+    pub fn pss_sign_with_salt(&self, pss: &Pss, message: &[u8], salt: &[u8]) -> Result<Vec<u8>> {
+        let em = pss.encode(message, salt, self.modulus.bits() as usize - 1)?;
+        let s = self.decrypt(&BigUint::from_bytes_be(&em))?;
+        i2osp(&s, self.get_public_key().modulus_len())
+    }
+}
+
+impl RsaPubKey {
+    pub fn pss_verify(&self, pss: &Pss, message: &[u8], signature: &[u8]) -> Result<()> {
+        let s = BigUint::from_bytes_be(signature);
+        if signature.len() != self.modulus_len() || s >= self.modulus {
+            return Err(anyhow!("invalid signature"));
+        }
+        let em_bits = self.modulus.bits() as usize - 1;
+        let em = i2osp(&self.encrypt(&s)?, em_bits.div_ceil(8))
+            .map_err(|_| anyhow!("invalid signature"))?;
+        pss.verify_encoding(message, &em, em_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptopal_util::hex_to_bytes;
+
+    fn hex(s: &str) -> Vec<u8> {
+        hex_to_bytes(s.to_string()).unwrap()
+    }
+
+    fn big(s: &str) -> BigUint {
+        BigUint::from_bytes_be(&hex(s))
+    }
+
+    #[test]
+    fn test_rsa_labs_vector() {
+        // the worked RSASSA-PSS example from the PKCS #1 v2.1 test vectors (pss-int.txt)
+        let key = RsaKey::from_primes(
+            big("d17f655bf27c8b16d35462c905cc04a26f37e2a67fa9c0ce0dced472394a0df743fe7f929e378efdb368eddff453cf007af6d948e0ade757371f8a711e278f6b"),
+            big("c6d92b6fee7414d1358ce1546fb62987530b90bd15e0f14963a5e2635adb69347ec0c01b2ab1763fd8ac1a592fb22757463a982425bb97a3a437c5bf86d03f2f"),
+            BigUint::from(65537u32),
+        )
+        .unwrap();
+        assert_eq!(
+            key.modulus,
+            big("a2ba40ee07e3b2bd2f02ce227f36a195024486e49c19cb41bbbdfbba98b22b0e577c2eeaffa20d883a76e65e394c69d4b3c05a1e8fadda27edb2a42bc000fe888b9b32c22d15add0cd76b3e7936e19955b220dd17d4ea904b1ec102b2e4de7751222aa99151024c7cb41cc5ea21d00eeb41f7c800834d2c6e06bce3bce7ea9a5")
+        );
+        let pss = Pss::new(Hash::Sha1);
+        let message = hex("859eef2fd78aca00308bdc471193bf55bf9d78db8f8a672b484634f3c9c26e6478ae10260fe0dd8c082e53a5293af2173cd50c6d5d354febf78b26021c25c02712e78cd4694c9f469777e451e7f8e9e04cd3739c6bbfedae487fb55644e9ca74ff77a53cb729802f6ed4a5ffa8ba159890fc");
+        let salt = hex("e3b5d5d002c1bce50c2b65ef88a188d83bce7e61");
+        let signature = hex("8daa627d3de7595d63056c7ec659e54406f10610128baae821c8b2a0f3936d54dc3bdce46689f6b7951bb18e840542769718d5715d210d85efbb596192032c42be4c29972c856275eb6d5a45f05f51876fc6743deddd28caec9bb30ea99e02c3488269604fe497f74ccd7c7fca1671897123cbd30def5d54a2b5536ad90a747e");
+        assert_eq!(
+            key.pss_sign_with_salt(&pss, &message, &salt).unwrap(),
+            signature
+        );
+        let public = key.get_public_key();
+        public.pss_verify(&pss, &message, &signature).unwrap();
+        assert!(public
+            .pss_verify(&pss, b"something else", &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn test_sha256_from_python_cryptography() {
+        // signatures of "attack at dawn" with our test key, SHA-256 and MGF1-SHA-256
+        let key =
+            RsaKey::from_pem(&std::fs::read_to_string("./data/rsa_pkcs8.pem").unwrap()).unwrap();
+        let public = key.get_public_key();
+        let no_salt = Pss::new(Hash::Sha256).with_salt_len(0);
+        // no salt, so nothing random: we have to come up with the same signature
+        let signature = hex("52bb2a9c0286fbc3aa111bc49bdb2dc1590aafed32ccb04d04ebc89491bec62c4a986216500be17f8f7fd3b7836a224c8e5f45e83798fd53d105eedaf6384cff87abb8b09b5c546d829932c0ce6fe7b14d37373537516d836897c62a14619d7447de5ace62758d4555f35fd9deffaf4b4113bcf1f42d606c1168f7e2798506ef");
+        assert_eq!(
+            key.pss_sign(&no_salt, b"attack at dawn").unwrap(),
+            signature
+        );
+        public
+            .pss_verify(&no_salt, b"attack at dawn", &signature)
+            .unwrap();
+
+        let salted = Pss::new(Hash::Sha256);
+        let signature = hex("168497ba34ff045797d9ee2d571b37213d71289be966a1762a7d4183994a442371e6139882bafcd8ba17f344e0ba4cb68392eb52bb1fab9b4de1fc4d8e2e86131963155bc211f2ee7907173f2fa76364cacf70a085b677b851e8aaedd3876dd4df5e2c1f7d54aa297290f3ae8346bc7d39ac7b96ac506680769ea20736d63d97");
+        public
+            .pss_verify(&salted, b"attack at dawn", &signature)
+            .unwrap();
+        // the salt length is part of the parameters
+        assert!(public
+            .pss_verify(&no_salt, b"attack at dawn", &signature)
+            .is_err());
+        let mgf1_sha1 = Pss {
+            mgf1_hash: Hash::Sha1,
+            ..salted.clone()
+        };
+        assert!(public
+            .pss_verify(&mgf1_sha1, b"attack at dawn", &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = RsaKey::new(256);
+        let public = key.get_public_key();
+        for pss in [
+            Pss::new(Hash::Sha1),
+            Pss::new(Hash::Sha256).with_salt_len(10),
+        ] {
+            let signature = key.pss_sign(&pss, b"hi mom").unwrap();
+            public.pss_verify(&pss, b"hi mom", &signature).unwrap();
+            // salted, so it's different every time
+            assert_ne!(key.pss_sign(&pss, b"hi mom").unwrap(), signature);
+            let mut tampered = signature.clone();
+            tampered[20] ^= 4;
+            assert!(public.pss_verify(&pss, b"hi mom", &tampered).is_err());
+            assert!(public.pss_verify(&pss, b"hi mum", &signature).is_err());
+        }
+        // 32 + 32 + 2 bytes doesn't fit in 511 bits
+        assert!(key.pss_sign(&Pss::new(Hash::Sha256), b"hi mom").is_err());
+    }
+
+    #[test]
+    fn test_no_e_3_forgery() {
+        let key = RsaKey::with_exponent(512, &BigUint::from(3u32));
+        let public = key.get_public_key();
+        let pss = Pss::new(Hash::Sha256);
+        let em_bits = public.modulus.bits() as usize - 1;
+        let k = public.modulus_len();
+
+        // no cube ends in 0xbc, and whether a cube does only depends on the last byte of what
+        // was cubed
+        assert!((0..256u32).all(|s| s.pow(3) % 256 != TRAILER as u32));
+
+        // challenge 42's trick: everything that matters at the top of the block, anything below,
+        // and a cube root rounded up. here, everything matters
+        let salt = [0x42; 32];
+        let em = BigUint::from_bytes_be(&pss.encode(b"hi mom", &salt, em_bits).unwrap());
+        for keep in [k / 3, k / 2, k] {
+            let garbage_bits = 8 * (k - keep) as u32;
+            let block = (&em >> garbage_bits << garbage_bits) + 1u32;
+            let forged = block.cbrt() + 1u32;
+            assert!(&forged * &forged * &forged < public.modulus);
+            let forged = i2osp(&forged, k).unwrap();
+            assert!(public.pss_verify(&pss, b"hi mom", &forged).is_err());
+        }
+    }
+}
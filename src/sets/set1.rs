@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::aes_fun::Key;
+    use crate::englishness::scorer::{QUADGRAMS, UNIGRAMS};
     use crate::{aes_fun, cryptopal_util, englishness};
 
     #[test]
@@ -36,21 +37,22 @@ mod tests {
         let input = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
         let output = "Cooking MC's like a pound of bacon";
         let input_bytes = cryptopal_util::hex_to_bytes(input.to_string()).unwrap();
-        let (_, cracked, _) = englishness::find_best_fixed_xor(input_bytes);
+        let (_, cracked, _) = englishness::find_best_fixed_xor(&input_bytes, &*QUADGRAMS);
         assert_eq!(cryptopal_util::bytes_to_ascii(&cracked).unwrap(), output);
     }
 
     #[test]
     fn s1c4_detect_single_byte_xor() {
-        let filename = "./data/4.txt";
-        let lines = cryptopal_util::read_lines_from_file(filename.to_string()).unwrap();
-        let (_, _, decoded_bytes) = englishness::find_which_is_fixed_xor(
-            lines
-                .iter()
-                .map(|hex| cryptopal_util::hex_to_bytes(hex.to_string()).unwrap())
-                .collect(),
-        );
-        let decoded_string = cryptopal_util::bytes_to_ascii(&decoded_bytes).unwrap();
+        let file = std::io::BufReader::new(std::fs::File::open("./data/4.txt").unwrap());
+        let found = englishness::hunt::hunt(
+            file,
+            |hex| cryptopal_util::hex_to_bytes(hex.to_string()),
+            &*QUADGRAMS,
+            1,
+        )
+        .unwrap();
+        let decoded_bytes = &found[0].plaintext;
+        let decoded_string = cryptopal_util::bytes_to_ascii(decoded_bytes).unwrap();
         assert_eq!(decoded_string, "Now that the party is jumping\n");
     }
 
@@ -72,7 +74,7 @@ mod tests {
     fn s1c6_break_repeating_key_xor() {
         let filename = "./data/6.txt";
         let bytes = cryptopal_util::read_bytes_from_b64_file(filename.to_string()).unwrap();
-        let (my_key, my_output) = englishness::break_repeating_key_xor(bytes);
+        let (my_key, my_output) = englishness::break_repeating_key_xor(bytes, &*UNIGRAMS);
         println!("{}", cryptopal_util::bytes_to_ascii(&my_output).unwrap());
         assert_eq!(
             cryptopal_util::bytes_to_ascii(&my_key).unwrap(),
@@ -14,7 +14,7 @@ mod tests {
 
     #[test]
     fn s4c26_break_ctr_bitflipping() {
-        assert!(aes_fun::challenge_16::attack().unwrap());
+        assert!(aes_fun::challenge_26::attack().unwrap());
     }
 
     #[test]
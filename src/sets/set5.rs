@@ -53,13 +53,14 @@ mod tests {
     #[test]
     fn s5c39_implement_rsa() {
         let m = BigUint::from(2u64);
-        let key = RsaKey {
-            _p: BigUint::from(3u64),
-            _q: BigUint::from(11u64),
-            modulus: BigUint::from(33u64),
-            public_exponent: BigUint::from(7u64),
-            private_exponent: BigUint::from(3u64),
-        };
+        let key = RsaKey::from_primes(
+            BigUint::from(3u64),
+            BigUint::from(11u64),
+            BigUint::from(7u64),
+        )
+        .unwrap();
+        assert_eq!(key.modulus, BigUint::from(33u64));
+        assert_eq!(key.private_exponent, BigUint::from(3u64));
         let c = key.encrypt(&m).unwrap();
         assert_eq!(c, BigUint::from(29u64));
         let m2 = key.decrypt(&c).unwrap();
@@ -1,5 +1,8 @@
 #[cfg(test)]
 mod tests {
+    use crate::aes_fun;
+    use crate::elliptic_curve;
+
     #[test]
     fn s8c57_diffie_hellman_small_subgroup_confinement() {
         unimplemented!();
@@ -32,22 +35,22 @@ mod tests {
 
     #[test]
     fn s8c63_key_recovery_attacks_on_gcm_with_repeated_nonces() {
-        unimplemented!();
+        aes_fun::challenge_63::attack().unwrap()
     }
 
     #[test]
     fn s8c64_key_recovery_attacks_on_gcm_with_a_truncated_mac() {
-        unimplemented!();
+        aes_fun::challenge_64::attack().unwrap();
     }
 
     #[test]
     fn s8c65_truncated_mac_gcm_revisited_improving_the_key_recovery_attack_via_ciphertext_length_extension(
     ) {
-        unimplemented!();
+        aes_fun::challenge_65::attack().unwrap();
     }
 
     #[test]
     fn s8c66_exploiting_implementation_errors_in_diffie_hellman() {
-        unimplemented!();
+        elliptic_curve::challenge_66::attack().unwrap();
     }
 }
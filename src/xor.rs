@@ -0,0 +1,377 @@
+// The xor "ciphers" that turn up in CTFs and malware, and how to get the key back when you know
+// some of the plaintext (an anchor: usually the file's magic number, "%PDF-" or "MZ" at the start).
+//
+//  - Repeating: the cryptopals one, key bytes round and round.
+//  - Rolling: one key byte that goes up by step every byte: k_i = start + step * i.
+//  - Feedback: each byte is also xored with the previous ciphertext byte,
+//    c_i = p_i ^ key[i % len] ^ c_{i-1} (and c_{-1} = 0). xor each ciphertext byte with the one
+//    before and it's a repeating key xor again.
+//
+// An anchor gives away the keystream wherever it sits. For rolling, two bytes of that is the whole
+// key. For repeating (and feedback, once it's unchained), it's the key bytes the anchor covers; the
+// key length comes from englishness::keysize, and any key bytes the anchor doesn't reach are broken
+// as single byte xors, with a scorer that suits the plaintext.
+
+use crate::cryptopal_util::repeating_key_xor;
+use crate::englishness::detectors::FILE_MAGIC;
+use crate::englishness::find_best_fixed_xor;
+use crate::englishness::keysize::{rank_keysizes, shortest_period, KeysizeCandidate, MAX_KEYSIZE};
+use crate::englishness::scorer::Scorer;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    Repeating,
+    Rolling,
+    Feedback,
+}
+
+impl Scheme {
+    pub const ALL: [Scheme; 3] = [Scheme::Repeating, Scheme::Rolling, Scheme::Feedback];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XorKey {
+    Repeating(Vec<u8>),
+    Rolling { start: u8, step: u8 },
+    Feedback(Vec<u8>),
+}
+
+/// plaintext we know is at offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anchor<'a> {
+    pub offset: usize,
+    pub plaintext: &'a [u8],
+}
+
+/// the magic numbers of every file type we know, at the start of the file
+pub fn file_anchors() -> Vec<Anchor<'static>> {
+    FILE_MAGIC
+        .iter()
+        .map(|file| Anchor {
+            offset: 0,
+            plaintext: file.magic,
+        })
+        .collect()
+}
+
+fn rolling_keystream(start: u8, step: u8, length: usize) -> Vec<u8> {
+    (0..length)
+        .map(|i| start.wrapping_add(step.wrapping_mul(i as u8)))
+        .collect()
+}
+
+// c_i ^ c_{i-1}: feedback ciphertext as a repeating key xor
+fn unchain(ciphertext: &[u8]) -> Vec<u8> {
+    let mut previous = 0;
+    ciphertext
+        .iter()
+        .map(|&c| {
+            let d = c ^ previous;
+            previous = c;
+            d
+        })
+        .collect()
+}
+
+impl XorKey {
+    pub fn scheme(&self) -> Scheme {
+        match self {
+            XorKey::Repeating(_) => Scheme::Repeating,
+            XorKey::Rolling { .. } => Scheme::Rolling,
+            XorKey::Feedback(_) => Scheme::Feedback,
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        match self {
+            XorKey::Repeating(key) => repeating_key_xor(plaintext, key),
+            XorKey::Rolling { start, step } => repeating_key_xor(
+                plaintext,
+                &rolling_keystream(*start, *step, plaintext.len()),
+            ),
+            XorKey::Feedback(key) => {
+                let mut previous = 0;
+                repeating_key_xor(plaintext, key)
+                    .into_iter()
+                    .map(|b| {
+                        previous ^= b;
+                        previous
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Vec<u8> {
+        match self {
+            XorKey::Repeating(_) | XorKey::Rolling { .. } => self.encrypt(ciphertext),
+            XorKey::Feedback(key) => repeating_key_xor(&unchain(ciphertext), key),
+        }
+    }
+}
+
+// the keystream under the anchor, or None if the anchor doesn't fit in the ciphertext
+fn anchor_keystream(ciphertext: &[u8], anchor: &Anchor) -> Option<Vec<u8>> {
+    let end = anchor.offset + anchor.plaintext.len();
+    if anchor.plaintext.is_empty() || end > ciphertext.len() {
+        return None;
+    }
+    Some(
+        ciphertext[anchor.offset..end]
+            .iter()
+            .zip(anchor.plaintext)
+            .map(|(c, p)| c ^ p)
+            .collect(),
+    )
+}
+
+fn solve_rolling(ciphertext: &[u8], anchor: &Anchor) -> Option<XorKey> {
+    let keystream = anchor_keystream(ciphertext, anchor)?;
+    if keystream.len() < 2 {
+        return None;
+    }
+    let step = keystream[1].wrapping_sub(keystream[0]);
+    let start = keystream[0].wrapping_sub(step.wrapping_mul(anchor.offset as u8));
+    let expected = rolling_keystream(start, step, anchor.offset + keystream.len());
+    (expected[anchor.offset..] == keystream[..]).then_some(XorKey::Rolling { start, step })
+}
+
+// the key of a repeating key xor, from the likeliest key length the anchor agrees with
+fn solve_repeating<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    keysizes: &[KeysizeCandidate],
+    columns: &ColumnCache,
+    anchor: &Anchor,
+    scorer: &S,
+) -> Option<Vec<u8>> {
+    let keystream = anchor_keystream(ciphertext, anchor)?;
+    for candidate in keysizes {
+        let keysize = candidate.keysize;
+        let mut key: Vec<Option<u8>> = vec![None; keysize];
+        let consistent = keystream.iter().enumerate().all(|(i, &k)| {
+            let slot = &mut key[(anchor.offset + i) % keysize];
+            *slot.get_or_insert(k) == k
+        });
+        if !consistent {
+            continue;
+        }
+        let key = key
+            .into_iter()
+            .enumerate()
+            .map(|(i, known)| {
+                known.unwrap_or_else(|| {
+                    *columns.borrow_mut().entry((keysize, i)).or_insert_with(|| {
+                        let column = ciphertext.iter().skip(i).step_by(keysize).copied();
+                        find_best_fixed_xor(&column.collect::<Vec<_>>(), scorer).0
+                    })
+                })
+            })
+            .collect::<Vec<u8>>();
+        return Some(shortest_period(&key).to_vec());
+    }
+    None
+}
+
+// (keysize, column) to the key byte that column breaks to
+type ColumnCache = RefCell<HashMap<(usize, usize), u8>>;
+
+// the ciphertext both ways round, with its key lengths ranked and the columns broken as they're
+// needed, so trying lots of anchors doesn't do it all again every time. (one scorer per Prepared)
+struct Prepared<'a> {
+    ciphertext: &'a [u8],
+    keysizes: Vec<KeysizeCandidate>,
+    columns: ColumnCache,
+    unchained: Vec<u8>,
+    unchained_keysizes: Vec<KeysizeCandidate>,
+    unchained_columns: ColumnCache,
+}
+
+impl<'a> Prepared<'a> {
+    fn new(ciphertext: &'a [u8]) -> Prepared<'a> {
+        let unchained = unchain(ciphertext);
+        Prepared {
+            ciphertext,
+            keysizes: rank_keysizes(ciphertext, MAX_KEYSIZE),
+            columns: RefCell::default(),
+            unchained_keysizes: rank_keysizes(&unchained, MAX_KEYSIZE),
+            unchained,
+            unchained_columns: RefCell::default(),
+        }
+    }
+
+    fn solve<S: Scorer + ?Sized>(
+        &self,
+        scheme: Scheme,
+        anchor: &Anchor,
+        scorer: &S,
+    ) -> Option<XorKey> {
+        match scheme {
+            Scheme::Repeating => solve_repeating(
+                self.ciphertext,
+                &self.keysizes,
+                &self.columns,
+                anchor,
+                scorer,
+            )
+            .map(XorKey::Repeating),
+            Scheme::Rolling => solve_rolling(self.ciphertext, anchor),
+            Scheme::Feedback => solve_repeating(
+                &self.unchained,
+                &self.unchained_keysizes,
+                &self.unchained_columns,
+                anchor,
+                scorer,
+            )
+            .map(XorKey::Feedback),
+        }
+    }
+}
+
+/// the key of a given scheme that puts the anchor where it says. the scorer is for key bytes the
+/// anchor doesn't reach (so it sees every keysize'th byte of plaintext, not contiguous text)
+pub fn solve<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    scheme: Scheme,
+    anchor: &Anchor,
+    scorer: &S,
+) -> Option<XorKey> {
+    Prepared::new(ciphertext).solve(scheme, anchor, scorer)
+}
+
+/// every scheme with every anchor, and the key whose plaintext the scorer likes best. returns
+/// (key, plaintext)
+pub fn solve_any<S: Scorer + ?Sized>(
+    ciphertext: &[u8],
+    anchors: &[Anchor],
+    scorer: &S,
+) -> Option<(XorKey, Vec<u8>)> {
+    let prepared = Prepared::new(ciphertext);
+    let mut best: Option<(f64, XorKey, Vec<u8>)> = None;
+    for scheme in Scheme::ALL {
+        for anchor in anchors {
+            let Some(key) = prepared.solve(scheme, anchor, scorer) else {
+                continue;
+            };
+            let plaintext = key.decrypt(ciphertext);
+            let score = scorer.score(&plaintext);
+            if best
+                .as_ref()
+                .is_none_or(|(best_score, _, _)| score > *best_score)
+            {
+                best = Some((score, key, plaintext));
+            }
+        }
+    }
+    best.map(|(_, key, plaintext)| (key, plaintext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::englishness::detectors::Alphabet;
+    use crate::englishness::scorer::{english_pdf, UNIGRAMS};
+
+    fn keys() -> Vec<XorKey> {
+        vec![
+            XorKey::Repeating(b"k3y!".to_vec()),
+            XorKey::Repeating(b"a much longer key than %PDF-".to_vec()),
+            XorKey::Rolling {
+                start: 0x37,
+                step: 1,
+            },
+            XorKey::Rolling {
+                start: 0xf0,
+                step: 0x1d,
+            },
+            XorKey::Feedback(b"KEY".to_vec()),
+            XorKey::Feedback(b"another longer key".to_vec()),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let plaintext = english_pdf();
+        for key in keys() {
+            let ciphertext = key.encrypt(&plaintext);
+            assert_ne!(ciphertext, plaintext);
+            assert_eq!(key.decrypt(&ciphertext), plaintext);
+        }
+        let feedback = XorKey::Feedback(vec![0x10]).encrypt(b"\x01\x02\x03");
+        assert_eq!(feedback, [0x11, 0x11 ^ 0x12, 0x11 ^ 0x12 ^ 0x13]);
+    }
+
+    #[test]
+    fn test_solve_with_pdf_anchor() {
+        let plaintext = english_pdf();
+        let anchor = Anchor {
+            offset: 0,
+            plaintext: b"%PDF-",
+        };
+        for key in keys() {
+            let ciphertext = key.encrypt(&plaintext);
+            let solved = solve(&ciphertext, key.scheme(), &anchor, &*UNIGRAMS);
+            assert_eq!(solved, Some(key));
+        }
+    }
+
+    #[test]
+    fn test_solve_any() {
+        let plaintext = english_pdf();
+        for key in keys() {
+            let ciphertext = key.encrypt(&plaintext);
+            let (solved, decrypted) = solve_any(&ciphertext, &file_anchors(), &*UNIGRAMS).unwrap();
+            assert_eq!(solved.scheme(), key.scheme());
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_mz_anchor() {
+        let mut exe = b"MZ\x90\x00\x03\x00\x00\x00\x04\x00\x00\x00\xff\xff".to_vec();
+        exe.extend_from_slice(b"This program cannot be run in DOS mode.");
+        let key = XorKey::Rolling {
+            start: 0x80,
+            step: 3,
+        };
+        let anchor = Anchor {
+            offset: 0,
+            plaintext: b"MZ",
+        };
+        let ciphertext = key.encrypt(&exe);
+        let solved = solve(
+            &ciphertext,
+            Scheme::Rolling,
+            &anchor,
+            &Alphabet::PrintableAscii,
+        );
+        assert_eq!(solved, Some(key));
+        // an anchor somewhere in the middle works too
+        let anchor = Anchor {
+            offset: 14,
+            plaintext: b"This program",
+        };
+        let solved = solve(
+            &ciphertext,
+            Scheme::Rolling,
+            &anchor,
+            &Alphabet::PrintableAscii,
+        );
+        assert_eq!(solved.unwrap().decrypt(&ciphertext), exe);
+        // anchors that don't fit
+        let anchor = Anchor {
+            offset: 50,
+            plaintext: b"past the end",
+        };
+        assert_eq!(
+            solve(
+                &ciphertext,
+                Scheme::Rolling,
+                &anchor,
+                &Alphabet::PrintableAscii
+            ),
+            None
+        );
+    }
+}